@@ -0,0 +1,62 @@
+use crate::mark::Mark;
+
+/// Whether columns in the error are counted from `0` or `1`.
+///
+/// `serde_yaml` reports 1-based columns. The [`error_line_column`] and
+/// [`context_line_column`] are always 1-based.
+///
+/// [`error_line_column`]: crate::ErrorAndContext::error_line_column
+/// [`context_line_column`]: crate::ErrorAndContext::context_line_column
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColumnBase {
+    /// The first column is `1`.
+    #[default]
+    OneBased,
+    /// The first column is `0`.
+    ZeroBased,
+}
+
+impl ColumnBase {
+    /// Returns `mark` with its column converted to be 1-based.
+    pub(crate) fn to_one_based(self, mark: Mark) -> Mark {
+        match (self, mark) {
+            (ColumnBase::ZeroBased, Mark::LineColumn { line, column }) => Mark::LineColumn {
+                line,
+                column: column.saturating_add(1),
+            },
+            (ColumnBase::OneBased, mark) | (ColumnBase::ZeroBased, mark @ Mark::Position(_)) => {
+                mark
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnBase;
+    use crate::mark::Mark;
+
+    #[test]
+    fn one_based_unchanged() {
+        assert_eq!(
+            Mark::LineColumn { line: 3, column: 3 },
+            ColumnBase::OneBased.to_one_based(Mark::LineColumn { line: 3, column: 3 })
+        );
+    }
+
+    #[test]
+    fn zero_based_column_incremented() {
+        assert_eq!(
+            Mark::LineColumn { line: 3, column: 3 },
+            ColumnBase::ZeroBased.to_one_based(Mark::LineColumn { line: 3, column: 2 })
+        );
+    }
+
+    #[test]
+    fn zero_based_position_unchanged() {
+        assert_eq!(
+            Mark::Position(42),
+            ColumnBase::ZeroBased.to_one_based(Mark::Position(42))
+        );
+    }
+}
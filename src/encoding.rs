@@ -0,0 +1,53 @@
+use std::borrow::Cow;
+
+/// Character encoding of YAML that was read as bytes.
+///
+/// `serde_yaml` reports columns in characters, so the byte offsets of the
+/// spans depend on how many bytes each character takes in the original
+/// encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, where a character takes 1 to 4 bytes.
+    #[default]
+    Utf8,
+    /// ISO-8859-1 (Latin-1), where each character is 1 byte.
+    Latin1,
+}
+
+impl Encoding {
+    /// Returns `bytes` decoded as a string.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub(crate) fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes),
+            Encoding::Latin1 => Cow::Owned(bytes.iter().copied().map(char::from).collect()),
+        }
+    }
+
+    /// Returns the byte offset in the encoded bytes of the `offset` into the
+    /// `decoded` string.
+    pub(crate) fn byte_offset(self, decoded: &str, offset: usize) -> usize {
+        match self {
+            Encoding::Utf8 => offset,
+            Encoding::Latin1 => decoded
+                .get(..offset)
+                .map_or(offset, |decoded_before| decoded_before.chars().count()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+
+    #[test]
+    fn latin1_decode_and_byte_offset() {
+        let bytes = b"caf\xe9: 1\n";
+        let decoded = Encoding::Latin1.decode(bytes);
+
+        assert_eq!("café: 1\n", decoded);
+        assert_eq!(6, Encoding::Latin1.byte_offset(&decoded, 7));
+        assert_eq!(7, Encoding::Utf8.byte_offset(&decoded, 7));
+    }
+}
@@ -1,22 +1,129 @@
-use miette::SourceOffset;
+use std::{fmt, ops::Range, sync::Arc};
+
+use miette::{
+    Diagnostic, LabeledSpan, MietteError, MietteSpanContents, NamedSource, Report, Severity,
+    SourceCode, SourceOffset, SourceSpan, SpanContents,
+};
+
+use crate::{
+    line_index::{self, LineIndex},
+    mark::{self, Mark},
+    message, scan, token, Encoding, ErrorAndContextBuilder, ErrorMessage, LocationPattern,
+    MultiError, PathSegment, SpanLen, SpannedError, YamlErrorKind,
+};
+
+/// [`ErrorAndContext::error_message`] when the error string has no message.
+const ERROR_MESSAGE_DEFAULT: &str = "YAML parse error";
+
+/// Default [`ErrorAndContext::context_message`].
+const CONTEXT_MESSAGE_DEFAULT: &str = "while parsing this value";
 
 /// The [`SourceOffset`]s of the error and the surrounding context based on the
 /// error display string.
+///
+/// This implements [`Diagnostic`], with the [`error_span`] as the primary label
+/// and the [`context_span`] as a secondary label. The source code is only
+/// stored when created through [`ErrorAndContext::new_owned`], otherwise attach
+/// it when creating the report:
+///
+/// ```rust
+/// # use yaml_error_context_hack::ErrorAndContext;
+/// # let file_contents = "value: abc\n";
+/// # let error = serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(file_contents)
+/// #     .unwrap_err();
+/// let error_and_context = ErrorAndContext::new(file_contents, &error);
+/// let report =
+///     miette::Report::new(error_and_context).with_source_code(file_contents.to_string());
+/// ```
+///
+/// [`error_span`]: Self::error_span
+/// [`context_span`]: Self::context_span
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(
+        from = "crate::error_and_context_repr::ErrorAndContextRepr",
+        into = "crate::error_and_context_repr::ErrorAndContextRepr"
+    )
+)]
 pub struct ErrorAndContext {
     /// The [`SourceOffset`] of the error.
     pub error_span: Option<SourceOffset>,
+    /// The length of the underline at the [`error_span`].
+    ///
+    /// This is inferred according to
+    /// [`ErrorAndContextBuilder::span_len_strategy`], which defaults to one
+    /// character. [`SpanLen::Token`] underlines the whole token, and quoted
+    /// values include their quotes. This is `None` when there is no
+    /// [`error_span`].
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`SpanLen::Token`]: crate::SpanLen::Token
+    pub error_span_len: Option<usize>,
     /// The error message with the source offsets truncated.
     ///
-    /// This is the text before the `" at "` text, because the source offsets in
-    /// the error message can be noise, e.g.
+    /// This is the error string with each `" at "` location removed, because
+    /// the source offsets in the error message can be noise, e.g.
     ///
     /// ```text
     /// "at line 2 column 11 at line 2 column 11 at line 2 column 3"
     /// ```
-    pub error_message: String,
+    ///
+    /// If the error string is only locations, this is `"YAML parse error"`.
+    pub error_message: ErrorMessage,
+    /// The path to the erroneous value, e.g. `"outer.inner"` or `"items[2]"`.
+    ///
+    /// This is the prefix of the [`error_message`] before the first `": "`, and
+    /// is `None` if the error has no location, or if that prefix does not look
+    /// like a path. Only keys made of alphanumeric characters, `_` and `-` are
+    /// detected.
+    ///
+    /// [`error_message`]: Self::error_message
+    pub path: Option<String>,
     /// The [`SourceOffset`] of the surrounding context.
     pub context_span: Option<SourceOffset>,
+    /// The length of the underline at the [`context_span`], such as the key of
+    /// the enclosing mapping.
+    ///
+    /// This is inferred the same way as the [`error_span_len`], and is `None`
+    /// when there is no [`context_span`].
+    ///
+    /// [`context_span`]: Self::context_span
+    /// [`error_span_len`]: Self::error_span_len
+    pub context_span_len: Option<usize>,
+    /// Label for the [`context_span`], only present when the `context_span` is.
+    ///
+    /// Defaults to `"while parsing this value"`, and can be set through
+    /// [`ErrorAndContextBuilder::context_message`].
+    ///
+    /// [`context_span`]: Self::context_span
+    pub context_message: Option<String>,
+    /// Name of the source, e.g. the file path.
+    ///
+    /// This is set through [`ErrorAndContextBuilder::source_name`] or
+    /// [`ErrorAndContext::with_source_name`], and is rendered with the
+    /// [`source`] when it is stored.
+    ///
+    /// [`source`]: Self::source
+    pub source_name: Option<String>,
+    /// The 1-based line and column of the error, as parsed from the error.
+    pub(crate) error_line_column: Option<(usize, usize)>,
+    /// The 1-based line and column of the surrounding context, as parsed from
+    /// the error.
+    pub(crate) context_line_column: Option<(usize, usize)>,
+    /// The YAML that was deserialized, if this was created with
+    /// [`ErrorAndContext::new_owned`].
+    ///
+    /// This is shared between clones, and compared by value.
+    pub(crate) source: Option<Arc<str>>,
+    /// The `(index, line, column)` of the error as reported by `serde_yaml`.
+    pub(crate) reported_location: Option<(usize, usize, usize)>,
+    /// The [`SourceOffset`] of the mapping key whose value has the error.
+    pub(crate) key_span: Option<SourceOffset>,
+    /// The [`SourceOffset`]s of each surrounding context, from the innermost
+    /// to the outermost.
+    pub(crate) context_spans: Vec<SourceOffset>,
 }
 
 impl ErrorAndContext {
@@ -28,354 +135,5402 @@ impl ErrorAndContext {
     /// This does a best-effort to find the actual error source offsets from the
     /// `Display` string of the error.
     pub fn new(file_contents: &str, error: &serde_yaml::Error) -> Self {
-        let error_string = format!("{error}");
-        let error_location_line_index_column = error.location().map(|error_location| {
+        Self::builder().build(file_contents, error)
+    }
+
+    /// Returns the error location and message, finding the locations in the
+    /// error string using the given [`LocationPattern`].
+    ///
+    /// Use this when `serde_yaml` error strings have been translated.
+    pub fn new_with_pattern(
+        file_contents: &str,
+        error: &serde_yaml::Error,
+        location_pattern: &LocationPattern,
+    ) -> Self {
+        Self::builder()
+            .location_pattern(location_pattern.clone())
+            .build(file_contents, error)
+    }
+
+    /// Returns the error location and message, holding onto the source so that
+    /// the diagnostic can be rendered without the original `file_contents`.
+    ///
+    /// This is useful to return the error up the stack and render it later.
+    pub fn new_owned(file_contents: impl Into<Arc<str>>, error: &serde_yaml::Error) -> Self {
+        let source = file_contents.into();
+        let mut error_and_context = Self::new(&source, error);
+        error_and_context.source = Some(source);
+        error_and_context
+    }
+
+    /// Returns the error location and message for YAML that was read as
+    /// bytes, such as with [`serde_yaml::from_reader`] or
+    /// [`serde_yaml::from_slice`].
+    ///
+    /// The bytes are decoded as UTF-8. Invalid sequences are replaced with
+    /// `U+FFFD`, which is 3 bytes long, so offsets after an invalid sequence
+    /// index into the decoded string rather than `bytes`.
+    pub fn new_from_bytes(bytes: &[u8], error: &serde_yaml::Error) -> Self {
+        Self::new_from_bytes_with_encoding(bytes, Encoding::Utf8, error)
+    }
+
+    /// Returns the error location and message for YAML that was read as
+    /// bytes in the given [`Encoding`].
+    ///
+    /// The bytes are decoded with `encoding`, and the spans are byte offsets
+    /// into `bytes`. The line and columns are in characters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    ///
+    /// use yaml_error_context_hack::{Encoding, ErrorAndContext, SourceOffset};
+    ///
+    /// let bytes = b"caf\xe9: abc\n";
+    /// let file_contents = bytes.iter().copied().map(char::from).collect::<String>();
+    /// let error = serde_yaml::from_str::<BTreeMap<String, u32>>(&file_contents).unwrap_err();
+    ///
+    /// let error_and_context =
+    ///     ErrorAndContext::new_from_bytes_with_encoding(bytes, Encoding::Latin1, &error);
+    ///
+    /// assert_eq!(Some(SourceOffset::from(6)), error_and_context.error_span);
+    /// ```
+    pub fn new_from_bytes_with_encoding(
+        bytes: &[u8],
+        encoding: Encoding,
+        error: &serde_yaml::Error,
+    ) -> Self {
+        let file_contents = encoding.decode(bytes);
+        let error_and_context = Self::new(&file_contents, error);
+        if encoding == Encoding::Utf8 {
+            return error_and_context;
+        }
+
+        let byte_offset = |offset: usize| encoding.byte_offset(&file_contents, offset);
+        let byte_span = |span: SourceOffset| SourceOffset::from(byte_offset(span.offset()));
+        let byte_len = |span: Option<SourceOffset>, len: Option<usize>| {
+            span.zip(len)
+                .map(|(span, len)| byte_offset(span.offset() + len) - byte_offset(span.offset()))
+        };
+
+        let ErrorAndContext {
+            error_span,
+            error_span_len,
+            context_span,
+            context_span_len,
+            key_span,
+            context_spans,
+            ..
+        } = error_and_context;
+
+        ErrorAndContext {
+            error_span: error_span.map(byte_span),
+            error_span_len: byte_len(error_span, error_span_len),
+            context_span: context_span.map(byte_span),
+            context_span_len: byte_len(context_span, context_span_len),
+            key_span: key_span.map(byte_span),
+            context_spans: context_spans.into_iter().map(byte_span).collect(),
+            ..error_and_context
+        }
+    }
+
+    /// Returns the error location and message, using `fallback_offset` as the
+    /// [`error_span`] when no location can be found for the error.
+    ///
+    /// This is useful for errors from [`serde_yaml::from_value`], which have
+    /// no location, when the caller has tracked where the value came from.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn new_with_fallback(
+        file_contents: &str,
+        error: &serde_yaml::Error,
+        fallback_offset: usize,
+    ) -> Self {
+        let mut error_and_context = Self::new(file_contents, error);
+        if error_and_context.error_span.is_none() {
+            let error_span =
+                SourceOffset::from(LineIndex::new(file_contents).offset_clamped(fallback_offset));
+            error_and_context.error_span = Some(error_span);
+            error_and_context.error_span_len =
+                Some(token::token_len(file_contents, error_span.offset()));
+        }
+        error_and_context
+    }
+
+    /// Returns the error location and message if `error` is a
+    /// [`serde_yaml::Error`], and `None` otherwise.
+    ///
+    /// This is useful when the error has been erased into a `Box<dyn Error>`.
+    pub fn try_from_dyn(
+        file_contents: &str,
+        error: &(dyn std::error::Error + 'static),
+    ) -> Option<Self> {
+        error
+            .downcast_ref::<serde_yaml::Error>()
+            .map(|error| Self::new(file_contents, error))
+    }
+
+    /// Returns the error location and message for an error from the document
+    /// at `document_index` in a multi-document YAML stream.
+    ///
+    /// `file_contents` is the whole stream. Locations from
+    /// [`serde_yaml::Deserializer::from_str`] are already within the stream;
+    /// if the error is not located within the document, such as when the
+    /// document was deserialized on its own, the location is found within the
+    /// document and offset to where the document begins.
+    pub fn new_for_document(
+        file_contents: &str,
+        document_index: usize,
+        error: &serde_yaml::Error,
+    ) -> Self {
+        let error_and_context = Self::new(file_contents, error);
+        let Some(document_range) = scan::document_ranges(file_contents)
+            .into_iter()
+            .nth(document_index)
+        else {
+            return error_and_context;
+        };
+        let is_in_document = error_and_context
+            .error_offset()
+            .is_some_and(|error_offset| document_range.contains(&error_offset));
+        if is_in_document {
+            return error_and_context;
+        }
+
+        let document = &file_contents[document_range.clone()];
+        let mut error_and_context =
+            Self::new(document, error).with_base_offset(document_range.start);
+        let line_index = LineIndex::new(file_contents);
+        error_and_context.error_line_column = error_and_context
+            .error_offset()
+            .map(|error_offset| line_index.line_column(error_offset));
+        error_and_context.context_line_column = error_and_context
+            .context_offset()
+            .map(|context_offset| line_index.line_column(context_offset));
+        error_and_context
+    }
+
+    /// Returns a builder to customize how the error location is parsed.
+    pub fn builder() -> ErrorAndContextBuilder {
+        ErrorAndContextBuilder::new()
+    }
+
+    /// Returns the error location and message from the `Display` string of a
+    /// `serde_yaml` error.
+    ///
+    /// `reported_location` is the `(index, line, column)` from the error's
+    /// [`location()`]. This is useful when the error has been passed across an
+    /// FFI boundary as a string, such as from JavaScript in WASM.
+    ///
+    /// [`location()`]: serde_yaml::Error::location
+    pub fn from_display(
+        file_contents: &str,
+        error_display: &str,
+        reported_location: Option<(usize, usize, usize)>,
+    ) -> Self {
+        Self::build_from_display(
+            &ErrorAndContextBuilder::default(),
+            file_contents,
+            error_display.to_string(),
+            reported_location,
+        )
+    }
+
+    /// Returns the error location and message from the `Display` string of a
+    /// `serde_yaml` error and its [`location()`].
+    ///
+    /// This is useful when the error message and location have been stored
+    /// without the error, such as when caching parsed errors, or to override
+    /// the location.
+    ///
+    /// [`location()`]: serde_yaml::Error::location
+    pub fn new_with_location(
+        file_contents: &str,
+        error_message: &str,
+        location: Option<serde_yaml::Location>,
+    ) -> Self {
+        Self::from_display(
+            file_contents,
+            error_message,
+            location.map(Self::location_line_index_column),
+        )
+    }
+
+    /// Returns the error location and message, using `extractor` to find the
+    /// 1-based `(line, column)` marks in the `Display` string of the error.
+    ///
+    /// The first mark is the error, and each following mark is a context,
+    /// from the innermost to the outermost. Use this when the error string is
+    /// in a format that the built in parsing does not handle.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::de::Error as _;
+    /// use yaml_error_context_hack::ErrorAndContext;
+    ///
+    /// let file_contents = "---\nouter:\n  field_1: 123\n";
+    /// let error = serde_yaml::Error::custom("invalid value (3:3)");
+    /// let error_and_context =
+    ///     ErrorAndContext::new_with_extractor(file_contents, &error, |_error_string| vec![(3, 3)]);
+    /// ```
+    pub fn new_with_extractor<F>(
+        file_contents: &str,
+        error: &serde_yaml::Error,
+        extractor: F,
+    ) -> Self
+    where
+        F: Fn(&str) -> Vec<(usize, usize)>,
+    {
+        let error_string = error.to_string();
+        let mut marks = extractor(&error_string)
+            .into_iter()
+            .map(|(line, column)| Mark::LineColumn { line, column });
+        let error_mark = marks.next();
+        let context_marks = marks.collect();
+
+        Self::build_from_marks(
+            &ErrorAndContextBuilder::default(),
+            file_contents,
+            error_string,
+            error_mark,
+            context_marks,
+            error.location().map(Self::location_line_index_column),
+        )
+    }
+
+    /// Returns the error location and message using the builder's options.
+    pub(crate) fn build(
+        builder: &ErrorAndContextBuilder,
+        file_contents: &str,
+        error: &serde_yaml::Error,
+    ) -> Self {
+        Self::build_from_display(
+            builder,
+            file_contents,
+            format!("{error}"),
+            error.location().map(Self::location_line_index_column),
+        )
+    }
+
+    /// Returns the `(index, line, column)` of a `serde_yaml` location.
+    fn location_line_index_column(location: serde_yaml::Location) -> (usize, usize, usize) {
+        (location.index(), location.line(), location.column())
+    }
+
+    /// Returns the error location and message from the error string and the
+    /// reported location, using the builder's options.
+    fn build_from_display(
+        builder: &ErrorAndContextBuilder,
+        file_contents: &str,
+        error_string: String,
+        error_location_line_index_column: Option<(usize, usize, usize)>,
+    ) -> Self {
+        let ErrorAndContextBuilder {
+            tab_width: _,
+            location_pattern,
+            source_name: _,
+            context_message: _,
+            span_len_strategy: _,
+            strip_ansi,
+            mark_order,
+            snap_to_token_start: _,
+            column_base,
+            max_message_len: _,
+        } = builder;
+        let error_string = if *strip_ansi {
+            message::strip_ansi(&error_string)
+        } else {
+            error_string
+        };
+
+        // libyaml errors with a `"while parsing ..."` context have a true location, and
+        // may have the context location after it.
+        let problem_and_context_marks =
+            mark::problem_and_context_marks(&error_string, location_pattern).map(
+                |(problem_mark, context_mark)| {
+                    mark_order.order(problem_mark, context_mark.into_iter().collect())
+                },
+            );
+        let error_and_context_marks = || {
+            let (error_mark, context_marks) =
+                mark::error_and_context_marks(&error_string, location_pattern);
+            mark_order.order(error_mark, context_marks)
+        };
+        let (error_mark, context_marks) =
+            match (problem_and_context_marks, error_location_line_index_column) {
+                (Some(problem_and_context_marks), _) => problem_and_context_marks,
+                // The `error_location` is not the true location, or there is no location. Extract
+                // it from the `Display` string.
+                //
+                // See:
+                //
+                // * <https://github.com/dtolnay/serde-yaml/blob/0.9.14/src/libyaml/error.rs#L65-L84>
+                // * <https://github.com/dtolnay/serde-yaml/blob/0.9.14/src/libyaml/error.rs#L141>
+                //
+                // Example error strings (truncated the beginning):
+                //
+                // ```text
+                // missing field `path` at line 2 column 12 at line 2 column 3
+                // unknown variant `~`, expected one of `a`, `b` at line 2 column 11 at line 2 column 11 at line 2 column 3
+                // did not find expected node content at position 42
+                // ```
+                (None, Some((0, 1, 1)) | None) => error_and_context_marks(),
+                // Some `serde_yaml` versions report an index of `0` with another line and column
+                // for the default location, so prefer the marks in the `Display` string if it has
+                // any.
+                (None, Some((0, line, column))) => match error_and_context_marks() {
+                    (error_mark @ Some(_), context_marks) => (error_mark, context_marks),
+                    (None, _) => (Some(Mark::LineColumn { line, column }), Vec::new()),
+                },
+                (None, Some((_, line, column))) => {
+                    (Some(Mark::LineColumn { line, column }), Vec::new())
+                }
+            };
+
+        let error_mark = error_mark.map(|mark| column_base.to_one_based(mark));
+        let context_marks = context_marks
+            .into_iter()
+            .map(|mark| column_base.to_one_based(mark))
+            .collect::<Vec<_>>();
+
+        Self::build_from_marks(
+            builder,
+            file_contents,
+            error_string,
+            error_mark,
+            context_marks,
+            error_location_line_index_column,
+        )
+    }
+
+    /// Returns the error location and message from the error and context marks,
+    /// using the builder's options.
+    ///
+    /// The context marks are from the innermost to the outermost.
+    fn build_from_marks(
+        builder: &ErrorAndContextBuilder,
+        file_contents: &str,
+        error_string: String,
+        error_mark: Option<Mark>,
+        context_marks: Vec<Mark>,
+        error_location_line_index_column: Option<(usize, usize, usize)>,
+    ) -> Self {
+        let ErrorAndContextBuilder {
+            tab_width,
+            location_pattern,
+            source_name: _,
+            context_message: _,
+            span_len_strategy,
+            strip_ansi: _,
+            mark_order: _,
+            snap_to_token_start,
+            column_base: _,
+            max_message_len,
+        } = builder;
+        let tab_width = *tab_width;
+        let line_index = LineIndex::new(file_contents);
+
+        // Empty contents have nothing to point at, such as when the file was truncated.
+        let (error_mark, context_marks) = if file_contents.is_empty() {
+            (None, Vec::new())
+        } else {
+            (error_mark, context_marks)
+        };
+
+        let mut error_span =
+            error_mark.map(|mark| SourceOffset::from(mark.offset(&line_index, tab_width)));
+        let mut error_line_column = error_mark.and_then(Mark::line_column);
+        let mut context_spans = context_marks
+            .iter()
+            .map(|mark| SourceOffset::from(mark.offset(&line_index, tab_width)))
+            .collect::<Vec<_>>();
+        let mut context_line_column = context_marks.first().copied().and_then(Mark::line_column);
+
+        // Errors for block scalars are reported at the `|` or `>` indicator, so point
+        // at the content of the scalar.
+        let block_scalar_body = error_span
+            .and_then(|error_span| scan::block_scalar_body(file_contents, error_span.offset()));
+        if let Some(block_scalar_body) = block_scalar_body {
+            error_span = Some(SourceOffset::from(block_scalar_body));
+            error_line_column = Some(line_index.line_column(block_scalar_body));
+        }
+
+        if *snap_to_token_start {
+            if let Some(error_offset) = error_span.map(|error_span| error_span.offset()) {
+                let token_start = token::token_start(file_contents, error_offset);
+                if token_start != error_offset {
+                    error_span = Some(SourceOffset::from(token_start));
+                    error_line_column = Some(line_index.line_column(token_start));
+                }
+            }
+        }
+
+        // Tab indentation errors are located at the start of the line or after the
+        // spaces, so point at the tab itself.
+        let error_message = message::into_without_marks(error_string, location_pattern);
+        if message::is_tab_indentation(&error_message) {
+            let tab_offset = error_span
+                .and_then(|error_span| scan::indentation_tab(file_contents, error_span.offset()));
+            if let Some(tab_offset) = tab_offset {
+                error_span = Some(SourceOffset::from(tab_offset));
+                error_line_column = Some(line_index.line_column(tab_offset));
+            }
+        }
+
+        // Duplicate key errors only locate the mapping, so find the duplicate key and
+        // its first occurrence in the file contents.
+        if context_spans.is_empty() {
+            let duplicate_and_first = message::duplicate_key(&error_message).and_then(|key| {
+                scan::duplicate_key(file_contents, key, error_span.map(|span| span.offset()))
+            });
+            if let Some((duplicate, first)) = duplicate_and_first {
+                error_span = Some(SourceOffset::from(duplicate.offset));
+                error_line_column = Some(line_index.line_column(duplicate.offset));
+                context_spans = vec![SourceOffset::from(first.offset)];
+                context_line_column = Some(line_index.line_column(first.offset));
+            }
+        }
+
+        // Unknown anchor errors locate the alias, so point the context at the closest
+        // anchor with the same name, e.g. one that is defined after the alias.
+        if context_spans.is_empty() && message::is_unknown_anchor(&error_message) {
+            let anchor_offset = error_span
+                .and_then(|error_span| {
+                    let alias_offset = error_span.offset();
+                    scan::alias_name(file_contents, alias_offset).map(|name| (alias_offset, name))
+                })
+                .and_then(|(alias_offset, name)| {
+                    scan::anchor_offsets(file_contents, name)
+                        .into_iter()
+                        .min_by_key(|anchor_offset| anchor_offset.abs_diff(alias_offset))
+                });
+            if let Some(anchor_offset) = anchor_offset {
+                context_spans = vec![SourceOffset::from(anchor_offset)];
+                context_line_column = Some(line_index.line_column(anchor_offset));
+            }
+        }
+
+        // Errors at the end of the input only locate the end, so point the context at
+        // where the incomplete document begins. Other errors at the end, such as an
+        // invalid type for an empty last value, are not from the input ending early.
+        if context_spans.is_empty() {
+            let end_offset =
+                error_span
+                    .map(|error_span| error_span.offset())
+                    .filter(|error_offset| {
+                        message::is_unexpected_end(&error_message)
+                            || (message::is_did_not_find_expected(&error_message)
+                                && file_contents
+                                    .get(*error_offset..)
+                                    .is_some_and(|rest| rest.trim().is_empty()))
+                    });
+            if let Some(end_offset) = end_offset {
+                let document_start = scan::document_start(file_contents, end_offset);
+                if document_start < end_offset {
+                    context_spans = vec![SourceOffset::from(document_start)];
+                    context_line_column = Some(line_index.line_column(document_start));
+                }
+            }
+        }
+
+        let mut error_and_context = Self::from_spans(
+            builder,
+            file_contents,
+            error_message,
+            error_span,
+            error_line_column,
+            context_spans,
+            context_line_column,
+        );
+        error_and_context.reported_location = error_location_line_index_column;
+
+        // A mapping found where another type was expected is located at its first
+        // key, so underline the whole block.
+        let is_map_found = message::invalid_type(&error_and_context.error_message)
+            .is_some_and(|(found, _expected)| found == "map");
+        if is_map_found && *span_len_strategy == SpanLen::Token {
+            let block_mapping_len = error_and_context
+                .error_span
+                .and_then(|error_span| scan::block_mapping_len(file_contents, error_span.offset()));
+            if let Some(block_mapping_len) = block_mapping_len {
+                error_and_context.error_span_len = Some(block_mapping_len);
+            }
+        }
+
+        // Missing fields in a flow mapping are located at its `{`, so underline the
+        // whole mapping.
+        let is_missing_field = message::missing_field(&error_and_context.error_message).is_some();
+        if is_missing_field && *span_len_strategy == SpanLen::Token {
+            let flow_collection_len = error_and_context.error_span.and_then(|error_span| {
+                token::flow_collection_len(file_contents, error_span.offset())
+            });
+            if let Some(flow_collection_len) = flow_collection_len {
+                error_and_context.error_span_len = Some(flow_collection_len);
+            }
+        }
+
+        // Underline the tab, as a tab is whitespace rather than a token.
+        let is_tab = error_and_context
+            .error_span
+            .and_then(|error_span| file_contents.get(error_span.offset()..))
+            .is_some_and(|error_onwards| error_onwards.starts_with('\t'));
+        if is_tab
+            && *span_len_strategy == SpanLen::Token
+            && message::is_tab_indentation(&error_and_context.error_message)
+        {
+            error_and_context.error_span_len = Some(1);
+        }
+
+        // Truncate after the message has been used to locate the spans.
+        if let Some(max_message_len) = *max_message_len {
+            let error_message = error_and_context.error_message.into_string();
+            error_and_context.error_message =
+                ErrorMessage::from(message::truncate(error_message, max_message_len));
+        }
+
+        error_and_context
+    }
+
+    /// Returns the error location and message for any [`SpannedError`], such
+    /// as a `serde_yaml` or `toml` error.
+    ///
+    /// Only the [`error_span`] is located. Use [`ErrorAndContext::new`] for
+    /// `serde_yaml` errors to also find the [`context_span`].
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn from_spanned<E>(file_contents: &str, error: &E) -> Self
+    where
+        E: SpannedError + ?Sized,
+    {
+        let error_range = error.span(file_contents);
+        let error_span = error_range
+            .as_ref()
+            .map(|error_range| SourceOffset::from(error_range.start));
+        let line_index = LineIndex::new(file_contents);
+
+        let mut error_and_context = Self::from_spans(
+            &ErrorAndContextBuilder::default(),
+            file_contents,
+            error.message(),
+            error_span,
+            error_span.map(|error_span| line_index.line_column(error_span.offset())),
+            Vec::new(),
+            None,
+        );
+        error_and_context.error_span_len = error_range.map(|error_range| error_range.len());
+        error_and_context
+    }
+
+    /// Returns the error location and message for a `serde_json` error.
+    ///
+    /// `serde_json` reports the correct line and column, and its column counts
+    /// bytes, not characters.
+    #[cfg(feature = "json")]
+    pub fn from_json_error(file_contents: &str, error: &serde_json::Error) -> Self {
+        let error_message =
+            message::into_without_marks(format!("{error}"), &LocationPattern::default());
+        let error_span = (error.line() != 0).then(|| {
+            SourceOffset::from(line_index::offset_byte_column(
+                file_contents,
+                error.line(),
+                error.column(),
+            ))
+        });
+
+        Self::from_spans(
+            &ErrorAndContextBuilder::default(),
+            file_contents,
+            error_message,
+            error_span,
+            error_span.map(|_| (error.line(), error.column())),
+            Vec::new(),
+            None,
+        )
+    }
+
+    /// Returns the `ErrorAndContext` for spans that have been computed, and the
+    /// error message with the marks removed.
+    fn from_spans(
+        builder: &ErrorAndContextBuilder,
+        file_contents: &str,
+        mut error_message: String,
+        error_span: Option<SourceOffset>,
+        error_line_column: Option<(usize, usize)>,
+        context_spans: Vec<SourceOffset>,
+        context_line_column: Option<(usize, usize)>,
+    ) -> Self {
+        let ErrorAndContextBuilder {
+            tab_width: _,
+            location_pattern: _,
+            source_name,
+            context_message,
+            span_len_strategy,
+            strip_ansi: _,
+            mark_order: _,
+            snap_to_token_start: _,
+            column_base: _,
+            max_message_len: _,
+        } = builder;
+
+        let error_span_len = error_span
+            .map(|error_span| span_len_strategy.span_len(file_contents, error_span.offset()));
+
+        if error_message.is_empty() {
+            error_message = ERROR_MESSAGE_DEFAULT.to_string();
+        }
+
+        // `serde_yaml` only renders a path for errors that have a location.
+        let path = error_span
+            .and(message::path(&error_message))
+            .map(str::to_string);
+
+        let key_span = path
+            .as_deref()
+            .and_then(message::path_last_key)
+            .zip(error_span)
+            .and_then(|(key, error_span)| {
+                scan::key_for_value(file_contents, key, error_span.offset())
+            })
+            .map(|key_occurrence| SourceOffset::from(key_occurrence.offset));
+
+        let context_span = context_spans.first().copied();
+        let context_span_len = context_span
+            .map(|context_span| span_len_strategy.span_len(file_contents, context_span.offset()));
+        let context_message = context_span.map(|_| {
+            context_message
+                .clone()
+                .unwrap_or_else(|| CONTEXT_MESSAGE_DEFAULT.to_string())
+        });
+
+        ErrorAndContext {
+            error_span,
+            error_span_len,
+            error_message: ErrorMessage::from(error_message),
+            path,
+            context_span,
+            context_span_len,
+            context_message,
+            source_name: source_name.clone(),
+            error_line_column,
+            context_line_column,
+            source: None,
+            reported_location: None,
+            key_span,
+            context_spans,
+        }
+    }
+
+    /// Returns this error with the [`error_span`], [`context_spans`], and
+    /// [`key_span`] shifted by `base` bytes.
+    ///
+    /// Use this when the YAML was extracted from a larger document, such as
+    /// front matter in Markdown, so that the spans are offsets into the larger
+    /// document. The line and columns are not shifted.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_spans`]: Self::context_spans
+    /// [`key_span`]: Self::key_span
+    pub fn with_base_offset(mut self, base: usize) -> Self {
+        let shift = |span: SourceOffset| SourceOffset::from(base + span.offset());
+        self.error_span = self.error_span.map(shift);
+        self.context_span = self.context_span.map(shift);
+        self.key_span = self.key_span.map(shift);
+        self.context_spans = self.context_spans.into_iter().map(shift).collect();
+        self
+    }
+
+    /// Returns this error with the spans and lines shifted into
+    /// `original_source`, which is the deserialized YAML with
+    /// `lines_stripped` leading lines.
+    ///
+    /// Use this when whole lines, such as a license header, were removed
+    /// before deserializing. The [`error_line_column`] and
+    /// [`context_line_column`] are recomputed against `original_source`, and
+    /// the [`source`] is replaced with `original_source` if it is stored.
+    ///
+    /// [`error_line_column`]: Self::error_line_column
+    /// [`context_line_column`]: Self::context_line_column
+    /// [`source`]: Self::source
+    pub fn with_line_offset(self, lines_stripped: usize, original_source: &str) -> Self {
+        let line_index = LineIndex::new(original_source);
+        let base = line_index.offset(lines_stripped + 1, 1, None);
+
+        let mut error_and_context = self.with_base_offset(base);
+        error_and_context.error_line_column = error_and_context
+            .error_offset()
+            .map(|error_offset| line_index.line_column(error_offset));
+        error_and_context.context_line_column = error_and_context
+            .context_offset()
+            .map(|context_offset| line_index.line_column(context_offset));
+        if error_and_context.source.is_some() {
+            error_and_context.source = Some(Arc::from(original_source));
+        }
+        error_and_context
+    }
+
+    /// Returns this error with the [`source_name`] set to `source_name`.
+    ///
+    /// This is useful to carry the file name with the error when processing
+    /// many files.
+    ///
+    /// [`source_name`]: Self::source_name
+    pub fn with_source_name(mut self, source_name: impl Into<String>) -> Self {
+        self.source_name = Some(source_name.into());
+        self
+    }
+
+    /// Returns this error with the [`error_message`] rewritten by `f`.
+    ///
+    /// The spans and [`path`] are unchanged.
+    ///
+    /// ```rust
+    /// # use yaml_error_context_hack::ErrorAndContext;
+    /// # let file_contents = "value: abc\n";
+    /// # let error = serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(file_contents)
+    /// #     .unwrap_err();
+    /// let error_and_context = ErrorAndContext::new(file_contents, &error)
+    ///     .map_message(|error_message| format!("config error: {error_message}"));
+    /// ```
+    ///
+    /// [`error_message`]: Self::error_message
+    /// [`path`]: Self::path
+    pub fn map_message<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(String) -> String,
+    {
+        self.error_message = ErrorMessage::from(f(self.error_message.into_string()));
+        self
+    }
+
+    /// Returns the 1-based line and column of the error, as parsed from the
+    /// error before conversion to a [`SourceOffset`].
+    ///
+    /// This is `None` if there is no error location, or if the location was
+    /// reported as a byte position.
+    pub fn error_line_column(&self) -> Option<(usize, usize)> {
+        self.error_line_column
+    }
+
+    /// Returns the 1-based line and column of the surrounding context, as
+    /// parsed from the error before conversion to a [`SourceOffset`].
+    ///
+    /// This is `None` if there is no context location, or if the location was
+    /// reported as a byte position.
+    pub fn context_line_column(&self) -> Option<(usize, usize)> {
+        self.context_line_column
+    }
+
+    /// Returns the `(index, line, column)` of the error as reported by
+    /// `serde_yaml`'s `location()`.
+    ///
+    /// This may be `(0, 1, 1)` even though the error is elsewhere, due to
+    /// [serde-yaml#153](https://github.com/dtolnay/serde-yaml/issues/153), and
+    /// is kept to help understand why the [`error_span`] differs from it.
+    ///
+    /// This is `None` if the error has no location.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn reported_location(&self) -> Option<(usize, usize, usize)> {
+        self.reported_location
+    }
+
+    /// Returns whether the location of the error was found.
+    ///
+    /// This is `false` for errors that have no location, such as errors from
+    /// [`serde_yaml::from_value`].
+    pub fn has_location(&self) -> bool {
+        self.error_span.is_some()
+    }
+
+    /// Returns the `(offset, length)` of the region from the earlier of the
+    /// [`error_span`] and [`context_span`] to the later one.
+    ///
+    /// This is `None` unless both spans are present.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn enclosing_span(&self) -> Option<(usize, usize)> {
+        let error_offset = self.error_span?.offset();
+        let context_offset = self.context_span?.offset();
+        let start = error_offset.min(context_offset);
+        let end = error_offset.max(context_offset);
+
+        Some((start, end - start))
+    }
+
+    /// Returns the half-open byte range of the underline at the [`error_span`].
+    ///
+    /// The range covers the [`error_span_len`], and is `None` when there is no
+    /// `error_span`.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`error_span_len`]: Self::error_span_len
+    pub fn error_range(&self) -> Option<Range<usize>> {
+        self.error_span.map(|error_span| {
+            let start = error_span.offset();
+            start..start + self.error_span_len.unwrap_or(0)
+        })
+    }
+
+    /// Returns the half-open byte range of the underline at the
+    /// [`context_span`].
+    ///
+    /// The range covers the [`context_span_len`], and is `None` when there is
+    /// no `context_span`.
+    ///
+    /// [`context_span`]: Self::context_span
+    /// [`context_span_len`]: Self::context_span_len
+    pub fn context_range(&self) -> Option<Range<usize>> {
+        self.context_span.map(|context_span| {
+            let start = context_span.offset();
+            start..start + self.context_span_len.unwrap_or(0)
+        })
+    }
+
+    /// Returns the start and end `(line, character)` of the [`error_span`] for
+    /// a Language Server Protocol `Range`.
+    ///
+    /// Lines and characters are 0-based, and characters count UTF-16 code
+    /// units. The range covers the [`error_span_len`], and is `None` when there
+    /// is no `error_span`.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`error_span_len`]: Self::error_span_len
+    pub fn to_lsp_range(&self, file_contents: &str) -> Option<((u32, u32), (u32, u32))> {
+        self.error_range().map(|error_range| {
             (
-                error_location.index(),
-                error_location.line(),
-                error_location.column(),
+                line_index::lsp_position(file_contents, error_range.start),
+                line_index::lsp_position(file_contents, error_range.end),
             )
-        });
-        let (error_span, context_span) = match error_location_line_index_column {
-            // The `error_location` is not the true location. Extract it from the `Display` string.
-            //
-            // See:
-            //
-            // * <https://github.com/dtolnay/serde-yaml/blob/0.9.14/src/libyaml/error.rs#L65-L84>
-            // * <https://github.com/dtolnay/serde-yaml/blob/0.9.14/src/libyaml/error.rs#L141>
-            //
-            // Example error strings (truncated the beginning):
-            //
-            // ```text
-            // missing field `path` at line 2 column 12 at line 2 column 3
-            // unknown variant `~`, expected one of `a`, `b` at line 2 column 11 at line 2 column 11 at line 2 column 3
-            // ```
-            Some((0, 1, 1)) => {
-                // TODO: This may also be "at position 123", but we don't support that yet.
-                let mut line_column_pairs =
-                    error_string.rsplit(" at line ").filter_map(|line_column| {
-                        let mut line_column_split = line_column.split(" column ");
-                        let line = line_column_split
-                            .next()
-                            .map(str::parse::<usize>)
-                            .and_then(Result::ok);
-                        let column = line_column_split
-                            .next()
-                            .map(str::parse::<usize>)
-                            .and_then(Result::ok);
-
-                        if let (Some(line), Some(column)) = (line, column) {
-                            Some((line, column))
-                        } else {
-                            None
-                        }
-                    });
+        })
+    }
 
-                let last_mark = line_column_pairs
-                    .next()
-                    .map(|(line, column)| SourceOffset::from_location(file_contents, line, column));
-                let second_to_last_mark = line_column_pairs
-                    .next()
-                    .map(|(line, column)| SourceOffset::from_location(file_contents, line, column));
-
-                match (second_to_last_mark, last_mark) {
-                    (error_span @ Some(_), context_span @ Some(_)) => (error_span, context_span),
-                    (None, error_span @ Some(_)) => (error_span, None),
-                    (Some(_), None) | (None, None) => (None, None),
-                }
+    /// Returns the [`SourceOffset`] of the mapping key whose value has the
+    /// error.
+    ///
+    /// This is found by scanning upwards from the [`error_span`] for the last
+    /// key in the [`path`]. This is best-effort, and is `None` when the path
+    /// does not end in a key, or the key cannot be found.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`path`]: Self::path
+    pub fn key_span(&self) -> Option<SourceOffset> {
+        self.key_span
+    }
+
+    /// Returns the byte offset of the [`error_span`].
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn error_offset(&self) -> Option<usize> {
+        self.error_span.map(|error_span| error_span.offset())
+    }
+
+    /// Returns the byte offset of the [`context_span`].
+    ///
+    /// [`context_span`]: Self::context_span
+    pub fn context_offset(&self) -> Option<usize> {
+        self.context_span.map(|context_span| context_span.offset())
+    }
+
+    /// Returns whether the [`error_span`] or [`context_span`] was found.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn has_span(&self) -> bool {
+        self.error_span.is_some() || self.context_span.is_some()
+    }
+
+    /// Returns whether both the [`error_span`] and [`context_span`] were found.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn has_both_spans(&self) -> bool {
+        self.error_span.is_some() && self.context_span.is_some()
+    }
+
+    /// Returns the [`SourceOffset`]s of each surrounding context, from the
+    /// innermost to the outermost.
+    ///
+    /// The [`context_span`] is the first of these. There may be more than one
+    /// when the error string has a location for each enclosing value.
+    ///
+    /// [`context_span`]: Self::context_span
+    pub fn context_spans(&self) -> &[SourceOffset] {
+        &self.context_spans
+    }
+
+    /// Returns the [`error_span`] and [`context_span`] that are present, sorted
+    /// by offset.
+    ///
+    /// The context usually precedes the error, so it is usually first.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn all_spans(&self) -> Vec<SourceOffset> {
+        let mut spans = self
+            .error_span
+            .into_iter()
+            .chain(self.context_span)
+            .collect::<Vec<_>>();
+        spans.sort_by_key(SourceOffset::offset);
+        spans
+    }
+
+    /// Returns the offset of each of the [`error_span`] and [`context_span`]
+    /// with the text of the line it falls on.
+    ///
+    /// The line text excludes the line break.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn marks_with_lines<'a>(
+        &'a self,
+        file_contents: &'a str,
+    ) -> impl Iterator<Item = (SourceOffset, &'a str)> + 'a {
+        self.error_span
+            .into_iter()
+            .chain(self.context_span)
+            .map(move |span| (span, line_index::line_text(file_contents, span.offset())))
+    }
+
+    /// Returns the start offset and length of the line containing the
+    /// [`error_span`], excluding the line break.
+    ///
+    /// This can be used to underline the whole line instead of the token.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn error_line_span(&self, file_contents: &str) -> Option<(usize, usize)> {
+        let error_span = self.error_span?;
+        let line_range = line_index::line_range(file_contents, error_span.offset());
+
+        Some((line_range.start, line_range.len()))
+    }
+
+    /// Returns a one line `file:line:column: message` string, like `rustc`'s
+    /// short message format.
+    ///
+    /// The line and column are computed from the [`error_span`], and are
+    /// omitted when there is no `error_span`, e.g. `file: message`.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn to_compact(&self, file_name: &str, file_contents: &str) -> String {
+        let error_message = &self.error_message;
+        match self.error_span {
+            Some(error_span) => {
+                let (line, column) = LineIndex::new(file_contents).line_column(error_span.offset());
+                format!("{file_name}:{line}:{column}: {error_message}")
             }
-            Some((_, line, column)) => (
-                Some(SourceOffset::from_location(file_contents, line, column)),
-                None,
-            ),
-            None => (None, None),
-        };
+            None => format!("{file_name}: {error_message}"),
+        }
+    }
+
+    /// Returns the line containing the [`error_span`], and a line of `^` under
+    /// the [`error_span_len`], like `rustc`.
+    ///
+    /// Tabs before the error are kept in the caret line so that it lines up
+    /// with the source line. Returns `None` when there is no `error_span`.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`error_span_len`]: Self::error_span_len
+    pub fn render_caret(&self, file_contents: &str) -> Option<String> {
+        let error_range = self.error_range()?;
+        let line_range = line_index::line_range(file_contents, error_range.start);
+        let line_text = &file_contents[line_range.clone()];
+        // The span may come from different contents, so it may not be on a character
+        // boundary.
+        let mut error_start = error_range.start.clamp(line_range.start, line_range.end);
+        while !file_contents.is_char_boundary(error_start) {
+            error_start -= 1;
+        }
+        let mut error_end = error_range.end.clamp(error_start, line_range.end);
+        while !file_contents.is_char_boundary(error_end) {
+            error_end -= 1;
+        }
+
+        let padding = file_contents[line_range.start..error_start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(file_contents[error_start..error_end].chars().count().max(1));
+
+        Some(format!("{line_text}\n{padding}{carets}"))
+    }
+
+    /// Returns the [`error_span`], [`error_message`], and [`context_span`],
+    /// moving the message out.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`error_message`]: Self::error_message
+    /// [`context_span`]: Self::context_span
+    pub fn into_parts(self) -> (Option<SourceOffset>, String, Option<SourceOffset>) {
+        (
+            self.error_span,
+            self.error_message.into_string(),
+            self.context_span,
+        )
+    }
+
+    /// Returns whether the [`error_message`] is equal to `error_message`.
+    ///
+    /// This is useful in tests that assert the message without pinning the
+    /// offsets.
+    ///
+    /// [`error_message`]: Self::error_message
+    pub fn message_eq(&self, error_message: &str) -> bool {
+        self.error_message == error_message
+    }
+
+    /// Returns whether the [`error_span`] points at the start of `needle` in
+    /// `file_contents`.
+    ///
+    /// This is useful in tests that assert the location without pinning the
+    /// offsets.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn points_at(&self, file_contents: &str, needle: &str) -> bool {
+        self.error_span
+            .and_then(|error_span| file_contents.get(error_span.offset()..))
+            .is_some_and(|onwards| onwards.starts_with(needle))
+    }
+
+    /// Returns the expected variants or fields listed in the error message.
+    ///
+    /// For example, the following returns `["One", "Two"]`:
+    ///
+    /// ```text
+    /// unknown variant `~`, expected `One` or `Two`
+    /// ```
+    ///
+    /// Returns an empty `Vec` if the message does not list any.
+    pub fn expected(&self) -> Vec<String> {
+        message::expected(&self.error_message)
+    }
+
+    /// Returns the closest [`expected`] field or variant to the unknown one,
+    /// for a "did you mean" hint.
+    ///
+    /// For example, the following returns `Some("name")`:
+    ///
+    /// ```text
+    /// unknown field `nam`, expected `name`
+    /// ```
+    ///
+    /// Returns `None` if the error is not an unknown field or variant, or no
+    /// expected identifier is close enough.
+    ///
+    /// [`expected`]: Self::expected
+    pub fn suggestion(&self) -> Option<String> {
+        message::suggestion(&self.error_message)
+    }
+
+    /// Returns the segments of the [`path`] to the erroneous value.
+    ///
+    /// Returns an empty `Vec` if there is no path.
+    ///
+    /// [`path`]: Self::path
+    pub fn path_segments(&self) -> Vec<PathSegment> {
+        self.path
+            .as_deref()
+            .map(PathSegment::from_path)
+            .unwrap_or_default()
+    }
+
+    /// Returns the category of this error, classified from the error message.
+    pub fn kind(&self) -> YamlErrorKind {
+        YamlErrorKind::from_message(&self.error_message)
+    }
+
+    /// Returns how severe this error is, based on its [`kind`].
+    ///
+    /// [`Severity::Warning`] means the error is recoverable, e.g. an unknown
+    /// field, not that deserialization did not fail. See
+    /// [`YamlErrorKind::severity`].
+    ///
+    /// [`kind`]: Self::kind
+    pub fn severity(&self) -> Severity {
+        self.kind().severity()
+    }
+
+    /// Returns a hint to fix this error, based on its [`kind`].
+    ///
+    /// For example:
+    ///
+    /// * Missing field: ``"add `field_2:` to the mapping"``
+    /// * Unknown field: ``"did you mean `name`?"``
+    /// * Unknown variant: ``"expected one of `One`, `Two`"``
+    /// * Invalid type: `"expected u32"`
+    /// * Duplicate key: ``"remove one of the `value` keys"``
+    /// * Tab indentation: `"indent with spaces instead of tabs"`
+    ///
+    /// Returns `None` for other errors, or if there is nothing to suggest.
+    ///
+    /// [`kind`]: Self::kind
+    pub fn help(&self) -> Option<String> {
+        let backticked = |identifiers: Vec<String>| {
+            identifiers
+                .iter()
+                .map(|identifier| format!("`{identifier}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self.kind() {
+            YamlErrorKind::MissingField => self
+                .missing_field()
+                .map(|field| format!("add `{field}:` to the mapping")),
+            YamlErrorKind::UnknownField => self
+                .suggestion()
+                .map(|suggestion| format!("did you mean `{suggestion}`?")),
+            YamlErrorKind::UnknownVariant => {
+                let expected = self.expected();
+                (!expected.is_empty()).then(|| format!("expected one of {}", backticked(expected)))
+            }
+            YamlErrorKind::InvalidType => message::invalid_type(&self.error_message)
+                .map(|(_found, expected)| format!("expected {expected}")),
+            YamlErrorKind::DuplicateKey => message::duplicate_key(&self.error_message)
+                .map(|key| format!("remove one of the `{key}` keys")),
+            YamlErrorKind::TabIndentation => {
+                Some(String::from("indent with spaces instead of tabs"))
+            }
+            YamlErrorKind::Other(_) => None,
+        }
+    }
+
+    /// Returns whether the document is nested too deeply for `serde_yaml` to
+    /// deserialize.
+    ///
+    /// The [`error_span`] is where the recursion limit was exceeded.
+    ///
+    /// [`error_span`]: Self::error_span
+    pub fn is_recursion_limit(&self) -> bool {
+        message::is_recursion_limit(&self.error_message)
+    }
+
+    /// Returns the found and expected types of an `invalid type` error.
+    ///
+    /// For example, the following returns `(r#"string "abc""#, "u32")`:
+    ///
+    /// ```text
+    /// invalid type: string "abc", expected u32
+    /// ```
+    ///
+    /// Returns `None` for other kinds of errors.
+    pub fn invalid_type_info(&self) -> Option<(String, String)> {
+        message::invalid_type(&self.error_message)
+            .map(|(found, expected)| (found.to_string(), expected.to_string()))
+    }
+
+    /// Returns the name of the missing field, e.g. `field_2` for:
+    ///
+    /// ```text
+    /// outer: missing field `field_2`
+    /// ```
+    ///
+    /// Returns `None` if the error is not a missing field error.
+    pub fn missing_field(&self) -> Option<String> {
+        message::missing_field(&self.error_message).map(str::to_string)
+    }
+
+    /// Returns the value within the first double quotes of the error message,
+    /// with escapes processed.
+    ///
+    /// For example, the following returns `"2023-01-01"`:
+    ///
+    /// ```text
+    /// invalid type: string "2023-01-01", expected i64
+    /// ```
+    ///
+    /// Returns `None` if the message has no quoted value.
+    pub fn found_value(&self) -> Option<String> {
+        message::quoted_value(&self.error_message)
+    }
+
+    /// Returns the YAML that was deserialized, if this was created with
+    /// [`ErrorAndContext::new_owned`].
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the primary [`LabeledSpan`] at the [`error_span`], labelled with
+    /// the [`error_message`].
+    ///
+    /// This covers the [`error_span_len`], and is `None` when there is no
+    /// `error_span`.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`error_span_len`]: Self::error_span_len
+    /// [`error_message`]: Self::error_message
+    pub fn error_label(&self) -> Option<LabeledSpan> {
+        self.error_span.map(|error_span| {
+            LabeledSpan::new_primary_with_span(
+                Some(self.error_message.to_string()),
+                SourceSpan::new(error_span, self.error_span_len.unwrap_or(0)),
+            )
+        })
+    }
+
+    /// Returns the [`LabeledSpan`] at the [`context_span`], labelled with the
+    /// [`context_message`].
+    ///
+    /// This covers the [`context_span_len`], and is `None` when there is no
+    /// `context_span`.
+    ///
+    /// [`context_span`]: Self::context_span
+    /// [`context_span_len`]: Self::context_span_len
+    /// [`context_message`]: Self::context_message
+    pub fn context_label(&self) -> Option<LabeledSpan> {
+        self.context_span.map(|context_span| {
+            LabeledSpan::new_with_span(
+                self.context_message.clone(),
+                SourceSpan::new(context_span, self.context_span_len.unwrap_or(0)),
+            )
+        })
+    }
+
+    /// Returns the [`error_label`] followed by the [`context_label`], skipping
+    /// either when its span is absent.
+    ///
+    /// This is shared by the [`Diagnostic`] impls, so the rendered labels are
+    /// the same wherever this error is reported.
+    ///
+    /// [`error_label`]: Self::error_label
+    /// [`context_label`]: Self::context_label
+    pub(crate) fn labels(&self) -> Vec<LabeledSpan> {
+        self.error_label()
+            .into_iter()
+            .chain(self.context_label())
+            .collect()
+    }
+
+    /// Returns a [`NamedSource`] of the file contents, for miette to render the
+    /// labels from the [`error_span`] and [`context_span`] against.
+    ///
+    /// The file contents are cloned into the `NamedSource`.
+    ///
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    pub fn named_source(file_contents: &str, name: impl AsRef<str>) -> NamedSource<String> {
+        NamedSource::new(name, file_contents.to_string())
+    }
+
+    /// Returns a `codespan_reporting` [`Diagnostic`] for this error.
+    ///
+    /// The [`error_span`] is the primary label, and the [`context_span`] is a
+    /// secondary label.
+    ///
+    /// [`Diagnostic`]: codespan_reporting::diagnostic::Diagnostic
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    #[cfg(feature = "codespan")]
+    pub fn to_codespan_diagnostic<FileId>(
+        &self,
+        file_id: FileId,
+    ) -> codespan_reporting::diagnostic::Diagnostic<FileId>
+    where
+        FileId: Copy,
+    {
+        use codespan_reporting::diagnostic::Label;
+
+        let error_label = self
+            .error_range()
+            .map(|error_range| Label::primary(file_id, error_range));
+        let context_label = self.context_range().map(|context_range| {
+            let label = Label::secondary(file_id, context_range);
+            match self.context_message.as_deref() {
+                Some(context_message) => label.with_message(context_message),
+                None => label,
+            }
+        });
+
+        codespan_reporting::diagnostic::Diagnostic::error()
+            .with_message(&self.error_message)
+            .with_labels_iter(error_label.into_iter().chain(context_label))
+    }
+
+    /// Returns an `ariadne` [`Report`] for this error.
+    ///
+    /// The [`error_span`] is the primary label, and the [`context_span`] is a
+    /// secondary label. Spans are byte ranges into the source identified by
+    /// `source_id`.
+    ///
+    /// [`Report`]: ariadne::Report
+    /// [`error_span`]: Self::error_span
+    /// [`context_span`]: Self::context_span
+    #[cfg(feature = "ariadne")]
+    pub fn to_ariadne_report<SourceId>(
+        &self,
+        source_id: SourceId,
+    ) -> ariadne::Report<'static, (SourceId, std::ops::Range<usize>)>
+    where
+        SourceId: Clone + fmt::Debug + std::hash::Hash + Eq,
+    {
+        use ariadne::{Config, IndexType, Label, ReportKind};
+
+        let error_label = self.error_range().map(|error_range| {
+            Label::new((source_id.clone(), error_range)).with_message(&self.error_message)
+        });
+        let context_label = self.context_range().map(|context_range| {
+            let label = Label::new((source_id.clone(), context_range)).with_order(1);
+            match self.context_message.as_deref() {
+                Some(context_message) => label.with_message(context_message),
+                None => label,
+            }
+        });
+        let report_offset = self.error_span.map_or(0, |error_span| error_span.offset());
+
+        ariadne::Report::build(ReportKind::Error, (source_id, report_offset..report_offset))
+            .with_config(Config::default().with_index_type(IndexType::Byte))
+            .with_message(&self.error_message)
+            .with_labels(error_label.into_iter().chain(context_label))
+            .finish()
+    }
+
+    /// Returns a [`MultiError`] with this error and `other`, to render both as
+    /// one diagnostic.
+    pub fn merge(self, other: ErrorAndContext) -> MultiError {
+        MultiError::from(vec![self, other])
+    }
+
+    /// Returns a [`Report`] for this error, with the source code attached.
+    ///
+    /// # Parameters
+    ///
+    /// * `source_name`: Name of the source, e.g. the file path.
+    /// * `source`: The YAML that was deserialized.
+    pub fn into_report(self, source_name: impl AsRef<str>, source: impl Into<String>) -> Report {
+        Report::new(self).with_source_code(NamedSource::new(source_name, source.into()))
+    }
+
+    /// Returns this error rendered by miette's [`GraphicalReportHandler`].
+    ///
+    /// This uses miette's default [`GraphicalTheme`], which depends on the
+    /// environment: ASCII characters are used unless stdout and stderr are
+    /// both terminals, and colors are also omitted when `NO_COLOR` is set. Use
+    /// [`render_graphical_themed`] for output that does not depend on the
+    /// environment, such as in tests.
+    ///
+    /// # Parameters
+    ///
+    /// * `file_name`: Name of the source, e.g. the file path.
+    /// * `file_contents`: The YAML that was deserialized.
+    ///
+    /// [`GraphicalReportHandler`]: miette::GraphicalReportHandler
+    /// [`GraphicalTheme`]: miette::GraphicalTheme
+    /// [`render_graphical_themed`]: Self::render_graphical_themed
+    #[cfg(feature = "fancy")]
+    pub fn render_graphical(&self, file_name: impl AsRef<str>, file_contents: &str) -> String {
+        self.render_graphical_themed(file_name, file_contents, miette::GraphicalTheme::default())
+    }
+
+    /// Returns this error rendered by miette's [`GraphicalReportHandler`] with
+    /// the given `theme`.
+    ///
+    /// # Parameters
+    ///
+    /// * `file_name`: Name of the source, e.g. the file path.
+    /// * `file_contents`: The YAML that was deserialized.
+    /// * `theme`: Characters and colors to render with, e.g.
+    ///   [`GraphicalTheme::unicode_nocolor`].
+    ///
+    /// [`GraphicalReportHandler`]: miette::GraphicalReportHandler
+    /// [`GraphicalTheme::unicode_nocolor`]: miette::GraphicalTheme::unicode_nocolor
+    #[cfg(feature = "fancy")]
+    pub fn render_graphical_themed(
+        &self,
+        file_name: impl AsRef<str>,
+        file_contents: &str,
+        theme: miette::GraphicalTheme,
+    ) -> String {
+        let report = self.clone().into_report(file_name, file_contents);
+
+        let mut rendered = String::new();
+        // Writing to a `String` does not fail.
+        let _ = miette::GraphicalReportHandler::new_themed(theme)
+            .render_report(&mut rendered, report.as_ref());
+        rendered
+    }
+}
+
+impl From<(&str, &serde_yaml::Error)> for ErrorAndContext {
+    fn from((file_contents, error): (&str, &serde_yaml::Error)) -> Self {
+        Self::new(file_contents, error)
+    }
+}
+
+impl fmt::Display for ErrorAndContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error_message.fmt(f)
+    }
+}
+
+impl std::error::Error for ErrorAndContext {}
+
+/// Reads from the [`source`], named by the [`source_name`].
+///
+/// [`source`]: ErrorAndContext::source
+/// [`source_name`]: ErrorAndContext::source_name
+impl SourceCode for ErrorAndContext {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let source = self.source.as_deref().ok_or(MietteError::OutOfBounds)?;
+        let contents = source.read_span(span, context_lines_before, context_lines_after)?;
+
+        match self.source_name.as_ref() {
+            Some(source_name) => Ok(Box::new(MietteSpanContents::new_named(
+                source_name.clone(),
+                contents.data(),
+                *contents.span(),
+                contents.line(),
+                contents.column(),
+                contents.line_count(),
+            ))),
+            None => Ok(contents),
+        }
+    }
+}
+
+/// The [`severity`] is [`Severity::Warning`] for recoverable errors such as
+/// unknown fields, even though deserialization failed. Reporters that treat
+/// warnings as success should check the [`kind`] instead.
+///
+/// [`severity`]: ErrorAndContext::severity
+/// [`kind`]: ErrorAndContext::kind
+impl Diagnostic for ErrorAndContext {
+    fn severity(&self) -> Option<Severity> {
+        Some(ErrorAndContext::severity(self))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        ErrorAndContext::help(self).map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source.as_ref().map(|_| self as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = ErrorAndContext::labels(self);
+
+        (!labels.is_empty()).then(|| Box::new(labels.into_iter()) as Box<dyn Iterator<Item = _>>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{
+        Diagnostic, GraphicalReportHandler, GraphicalTheme, LabeledSpan, Severity, SourceOffset,
+        SourceSpan,
+    };
+    use serde::{de::Error as _, Deserialize, Serialize};
+
+    use super::ErrorAndContext;
+    use crate::{
+        ColumnBase, Encoding, ErrorMessage, LocationPattern, MarkOrder, PathSegment, SpanLen,
+        YamlErrorKind,
+    };
+
+    #[test]
+    fn returns_source_offsets_for_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"---
+outer:
+  field_1: 123
+# ^
+# '--- field_2 missing the first character of the first type that has `#[serde(flatten)]`.
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let loc_line = 3;
+        let loc_col = 3; // index 2 is column 3
+
+        assert_eq!(
+            "outer: missing field `field_2` at line 3 column 3",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(
+                    file_contents,
+                    loc_line,
+                    loc_col
+                )),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: missing field `field_2`"),
+                path: Some("outer".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((3, 3)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((13, 3, 3)),
+                key_span: Some(SourceOffset::from(4)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn marks_with_lines_for_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            vec![(
+                SourceOffset::from_location(file_contents, 3, 3),
+                "  field_1: 123"
+            )],
+            error_and_context
+                .marks_with_lines(file_contents)
+                .collect::<Vec<_>>(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn marks_with_lines_for_error_and_context() {
+        let file_contents = "---\r\nouter:\r\n  field_1: 123\r\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            vec![
+                (SourceOffset::from(15), "  field_1: 123"),
+                (SourceOffset::from(5), "outer:"),
+            ],
+            error_and_context
+                .marks_with_lines(file_contents)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn key_span_and_error_span_for_invalid_type() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            field: u32,
+        }
+
+        let file_contents = "field: \"x\"\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(SourceOffset::from(0)),
+            error_and_context.key_span(),
+            "{error}"
+        );
+        assert_eq!(
+            Some(SourceOffset::from(7)),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn key_span_none_without_path() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.key_span());
+    }
+
+    #[test]
+    fn error_offset_and_context_offset() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Some(13), error_and_context.error_offset());
+        assert_eq!(Some(4), error_and_context.context_offset());
+    }
+
+    #[test]
+    fn has_span_for_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error_and_context.has_span(), "{error}");
+        assert!(!error_and_context.has_both_spans(), "{error}");
+    }
+
+    #[test]
+    fn has_span_false_without_location() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(!error_and_context.has_span());
+        assert!(!error_and_context.has_both_spans());
+
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        assert!(ErrorAndContext::new(file_contents, &error).has_both_spans());
+    }
+
+    #[test]
+    fn all_spans_sorted_ascending() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        [
+            "invalid value at line 3 column 3 at line 2 column 1",
+            "invalid value at line 2 column 1 at line 3 column 3",
+        ]
+        .into_iter()
+        .for_each(|error_string| {
+            let error = serde_yaml::Error::custom(error_string);
+            let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+            let offsets = error_and_context
+                .all_spans()
+                .into_iter()
+                .map(|span| span.offset())
+                .collect::<Vec<_>>();
+            assert_eq!(vec![4, 13], offsets, "{error}");
+            assert!(offsets.windows(2).all(|pair| pair[0] <= pair[1]));
+        });
+    }
+
+    #[test]
+    fn error_line_span_covers_one_line() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let (start, len) = error_and_context.error_line_span(file_contents).unwrap();
+        assert_eq!("  field_1: 123", &file_contents[start..start + len]);
+    }
+
+    #[test]
+    fn error_line_span_for_last_line_without_newline() {
+        let file_contents = "---\nouter:\n  field_1: 123";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 12");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some((11, 14)),
+            error_and_context.error_line_span(file_contents)
+        );
+    }
+
+    #[test]
+    fn error_line_span_none_without_error_span() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.error_line_span(file_contents));
+    }
+
+    #[test]
+    fn returns_source_offsets_for_missing_field_for_flattened_struct() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Inner {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"---
+outer:
+  # inner
+  field_1: 123
+# ^
+# '-- field_2 missing on the first character of the wrapping first type that has `#[serde(flatten)]`.
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let loc_line = 4;
+        let loc_col = 3; // index 2 is column 3
+
+        assert_eq!(
+            "outer: missing field `field_2` at line 4 column 3",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(
+                    file_contents,
+                    loc_line,
+                    loc_col
+                )),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: missing field `field_2`"),
+                path: Some("outer".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((4, 3)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((23, 4, 3)),
+                key_span: Some(SourceOffset::from(4)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_missing_field_for_nested_flattened_struct() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Inner {
+            #[serde(flatten)]
+            inner_inner: InnerInner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct InnerInner {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"---
+outer:
+  # inner, inner_inner
+  field_1: 123
+# ^
+# '-- field_2 missing on the first character of the first type that has `#[serde(flatten)]`.
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let loc_line = 4;
+        let loc_col = 3; // index 2 is column 3
+
+        assert_eq!(
+            "outer: missing field `field_2` at line 4 column 3",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(
+                    file_contents,
+                    loc_line,
+                    loc_col
+                )),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: missing field `field_2`"),
+                path: Some("outer".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((4, 3)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((36, 4, 3)),
+                key_span: Some(SourceOffset::from(4)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_missing_field_for_nested_flattened_struct_2() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Inner {
+            inner_outer: InnerOuter,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct InnerOuter {
+            #[serde(flatten)]
+            inner_inner: InnerInner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct InnerInner {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"---
+outer:
+  # inner
+  inner_outer:
+    # inner_inner
+    field_1: 123
+# ^
+# '-- field_2 is always marked as missing on the first character of the
+#     first wrapping type. Ideally it would point to `field_1`'s position (line 6 col 5).
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let loc_line = 4;
+        let loc_col = 3; // index 4 is column 3
+
+        assert_eq!(
+            "outer: missing field `field_2` at line 4 column 3",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(
+                    file_contents,
+                    loc_line,
+                    loc_col
+                )),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: missing field `field_2`"),
+                path: Some("outer".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((4, 3)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((23, 4, 3)),
+                key_span: Some(SourceOffset::from(4)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_null_variant() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        enum Inner {
+            One { value: u32 },
+            Two { value: u32 },
+        }
+
+        let file_contents = r#"---
+outer:
+  inner: ~ # null variant
+#        ^
+#        '-- source offset is here.
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let loc_line = 3;
+        let loc_col = 10; // index 9 is column 10
+
+        assert_eq!(
+            "outer.inner: unknown variant `~`, expected `One` or `Two` at line 3 column 10",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(
+                    file_contents,
+                    loc_line,
+                    loc_col
+                )),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from(
+                    "outer.inner: unknown variant `~`, expected `One` or `Two`"
+                ),
+                path: Some("outer.inner".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((3, 10)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((20, 3, 10)),
+                key_span: Some(SourceOffset::from(13)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_position() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at position 22");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from(22)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("invalid value"),
+                path: None,
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: None,
+                context_line_column: None,
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_line_column_and_position() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3 at position 22");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(file_contents, 3, 3)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("invalid value"),
+                path: None,
+                context_span: Some(SourceOffset::from(22)),
+                context_span_len: Some(1),
+                context_message: Some("while parsing this value".to_string()),
+                source_name: None,
+                error_line_column: Some((3, 3)),
+                context_line_column: None,
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: vec![SourceOffset::from(22)],
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_position_and_line_column() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at position 22 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from(22)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("invalid value"),
+                path: None,
+                context_span: Some(SourceOffset::from_location(file_contents, 2, 1)),
+                context_span_len: Some(1),
+                context_message: Some("while parsing this value".to_string()),
+                source_name: None,
+                error_line_column: None,
+                context_line_column: Some((2, 1)),
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: vec![SourceOffset::from_location(file_contents, 2, 1)],
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn span_len_strategy_token_and_line() {
+        let file_contents = "---\nouter:\n  value: \"abc def\" # comment\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 10");
+        let error_text = |span_len_strategy| {
+            let error_and_context = ErrorAndContext::builder()
+                .span_len_strategy(span_len_strategy)
+                .build(file_contents, &error);
+            error_and_context
+                .error_range()
+                .map(|error_range| &file_contents[error_range])
+        };
+
+        assert_eq!(Some("\"abc def\""), error_text(SpanLen::Token));
+        assert_eq!(Some("\"abc def\" # comment"), error_text(SpanLen::Line));
+        assert_eq!(Some("\""), error_text(SpanLen::Char));
+    }
+
+    #[test]
+    fn returns_error_span_len_covering_key_token() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"---
+outer:
+  field_1: 123
+"#;
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_offset = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            "field_1",
+            &file_contents[error_offset..error_offset + error_span_len]
+        );
+    }
+
+    #[test]
+    fn returns_error_span_len_covering_null_token() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        enum Inner {
+            One,
+            Two,
+        }
+
+        let file_contents = "inner: ~ # null variant\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_offset = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            "~",
+            &file_contents[error_offset..error_offset + error_span_len]
+        );
+    }
+
+    #[test]
+    fn returns_error_span_len_including_quotes() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: \"a \\\" b\" # quoted\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_offset = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            "\"a \\\" b\"",
+            &file_contents[error_offset..error_offset + error_span_len]
+        );
+    }
+
+    #[test]
+    fn error_message_defaults_when_only_locations() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!("YAML parse error", error_and_context.error_message);
+        assert_eq!(
+            Some(SourceOffset::from_location(file_contents, 3, 3)),
+            error_and_context.error_span
+        );
+        assert_eq!(
+            Some(SourceOffset::from_location(file_contents, 2, 1)),
+            error_and_context.context_span
+        );
+    }
+
+    #[test]
+    fn display_is_error_message() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!("invalid value", error_and_context.to_string());
+    }
+
+    #[test]
+    fn diagnostic_labels_error_span_and_context_span() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let labels = Diagnostic::labels(&error_and_context).map(Iterator::collect::<Vec<_>>);
+
+        assert_eq!(
+            Some(vec![
+                LabeledSpan::new_primary_with_span(
+                    Some("invalid value".to_string()),
+                    SourceSpan::new(SourceOffset::from(13), 1),
+                ),
+                LabeledSpan::new_with_span(
+                    Some("while parsing this value".to_string()),
+                    SourceSpan::new(SourceOffset::from(4), 1),
+                ),
+            ]),
+            labels
+        );
+    }
+
+    #[test]
+    fn context_label_underlines_enclosing_key() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let context_label = error_and_context.context_label().unwrap();
+        let context_range = context_label.offset()..context_label.offset() + context_label.len();
+        assert_eq!("outer", &file_contents[context_range]);
+        assert_eq!(Some(5), error_and_context.context_span_len);
+    }
+
+    #[test]
+    fn diagnostic_labels_none_when_no_spans() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(Diagnostic::labels(&error_and_context).is_none());
+    }
+
+    #[test]
+    fn error_label_and_context_label() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let error_label = error_and_context.error_label().unwrap();
+        let context_label = error_and_context.context_label().unwrap();
+
+        assert_eq!(13, error_label.offset());
+        assert_eq!(1, error_label.len());
+        assert_eq!(Some("invalid value"), error_label.label());
+        assert!(error_label.primary());
+        assert_eq!(4, context_label.offset());
+        assert_eq!(Some("while parsing this value"), context_label.label());
+        assert!(!context_label.primary());
+    }
+
+    #[test]
+    fn error_label_and_context_label_none_when_no_spans() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.error_label());
+        assert_eq!(None, error_and_context.context_label());
+    }
+
+    #[test]
+    fn labels_len_matches_spans_present() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let labels = ErrorAndContext::new(file_contents, &error).labels();
+        assert_eq!(2, labels.len());
+        assert!(labels[0].primary());
+        assert!(!labels[1].primary());
+
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let labels = ErrorAndContext::new(file_contents, &error).labels();
+        assert_eq!(1, labels.len());
+        assert!(labels[0].primary());
+
+        let error = serde_yaml::Error::custom("invalid value");
+        assert!(ErrorAndContext::new(file_contents, &error)
+            .labels()
+            .is_empty());
+    }
+
+    #[test]
+    fn into_report_renders_source_name_and_underline() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let report =
+            ErrorAndContext::new(file_contents, &error).into_report("config.yaml", file_contents);
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            r#"
+  × value: invalid type: string "abc", expected u32
+   ╭─[config.yaml:1:8]
+ 1 │ value: abc
+   ·        ┬
+   ·        ╰── value: invalid type: string "abc", expected u32
+   ╰────
+  help: expected u32
+"#,
+            rendered
+        );
+    }
+
+    #[cfg(feature = "fancy")]
+    #[test]
+    fn render_graphical_includes_file_name_and_caret() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let rendered = ErrorAndContext::new(file_contents, &error).render_graphical_themed(
+            "config.yaml",
+            file_contents,
+            GraphicalTheme::unicode_nocolor(),
+        );
+
+        assert_eq!(
+            r#"
+  × value: invalid type: string "abc", expected u32
+   ╭─[config.yaml:1:8]
+ 1 │ value: abc
+   ·        ┬
+   ·        ╰── value: invalid type: string "abc", expected u32
+   ╰────
+  help: expected u32
+"#,
+            rendered
+        );
+    }
+
+    #[cfg(feature = "fancy")]
+    #[test]
+    fn render_graphical_uses_default_theme() {
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::Error::custom("invalid value at line 1 column 8");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            error_and_context.render_graphical_themed(
+                "config.yaml",
+                file_contents,
+                GraphicalTheme::default()
+            ),
+            error_and_context.render_graphical("config.yaml", file_contents)
+        );
+    }
+
+    #[test]
+    fn new_owned_renders_after_file_contents_dropped() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        fn load() -> Result<Config, Box<ErrorAndContext>> {
+            let file_contents = String::from("value: abc\n");
+            serde_yaml::from_str::<Config>(&file_contents).map_err(|error| {
+                Box::new(ErrorAndContext::new_owned(file_contents.as_str(), &error))
+            })
+        }
+
+        let error_and_context = load().unwrap_err();
+        let report = miette::Report::new_boxed(error_and_context);
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            r#"
+  × value: invalid type: string "abc", expected u32
+   ╭────
+ 1 │ value: abc
+   ·        ┬
+   ·        ╰── value: invalid type: string "abc", expected u32
+   ╰────
+  help: expected u32
+"#,
+            rendered
+        );
+    }
+
+    #[test]
+    fn new_with_fallback_uses_fallback_offset_without_location() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let value = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap();
+        let error = serde_yaml::from_value::<Config>(value).unwrap_err();
+        let error_and_context = ErrorAndContext::new_with_fallback(file_contents, &error, 7);
+
+        assert!(error.location().is_none());
+        assert_eq!(Some(SourceOffset::from(7)), error_and_context.error_span);
+        assert_eq!(Some(3), error_and_context.error_span_len);
+    }
+
+    #[test]
+    fn new_with_fallback_keeps_location() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new_with_fallback(file_contents, &error, 0);
+
+        assert_eq!(
+            ErrorAndContext::new(file_contents, &error),
+            error_and_context
+        );
+    }
+
+    #[test]
+    fn with_base_offset_shifts_spans() {
+        let front_matter = "---\nouter:\n  field_1: 123\n";
+        let document = format!("{}\n```yaml\n{front_matter}```\n", "#".repeat(111));
+        let base = document.find(front_matter).unwrap();
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(front_matter, &error).with_base_offset(base);
+
+        assert_eq!(120, base);
+        assert_eq!(Some(SourceOffset::from(133)), error_and_context.error_span);
+        assert_eq!(
+            Some(SourceOffset::from(124)),
+            error_and_context.context_span
+        );
+        assert_eq!("field_1", &document[133..133 + 7]);
+    }
+
+    #[test]
+    fn with_line_offset_maps_to_original_source() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+        }
+
+        let original_source = "# Copyright\n#\n# Licensed under MIT or Apache-2.0.\n#\n\n\
+            outer:\n  field_1: abc\n";
+        let file_contents = original_source.splitn(6, '\n').last().unwrap();
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context =
+            ErrorAndContext::new_owned(file_contents, &error).with_line_offset(5, original_source);
+
+        assert!(
+            error_and_context.points_at(original_source, "abc"),
+            "{error}"
+        );
+        assert_eq!(
+            Some((7, 12)),
+            error_and_context.error_line_column,
+            "{error}"
+        );
+        assert_eq!(Some(original_source), error_and_context.source(), "{error}");
+    }
+
+    #[test]
+    fn with_line_offset_zero_lines_unchanged() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            error_and_context.clone(),
+            error_and_context.with_line_offset(0, file_contents)
+        );
+    }
+
+    #[test]
+    fn map_message_only_changes_message() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("outer: invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let error_and_context_mapped = error_and_context
+            .clone()
+            .map_message(|error_message| format!("config error: {error_message}"));
+
+        assert_eq!(
+            "config error: outer: invalid value",
+            error_and_context_mapped.error_message
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_message: error_and_context.error_message.clone(),
+                ..error_and_context_mapped
+            },
+            error_and_context
+        );
+    }
+
+    #[test]
+    fn new_owned_stores_source() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new_owned(file_contents, &error);
+
+        assert_eq!(Some(file_contents), error_and_context.source());
+        assert_eq!(None, ErrorAndContext::new(file_contents, &error).source());
+    }
+
+    #[test]
+    fn new_owned_clone_shares_source() {
+        let file_contents = "value: 1\n".repeat(100_000) + "other: abc\n";
+        let error = serde_yaml::Error::custom("invalid value at line 100001 column 8");
+        let error_and_context = ErrorAndContext::new_owned(file_contents.as_str(), &error);
+        let error_and_context_clone = error_and_context.clone();
+
+        assert_eq!(
+            error_and_context.source().map(str::as_ptr),
+            error_and_context_clone.source().map(str::as_ptr)
+        );
+        assert_eq!(error_and_context, error_and_context_clone);
+        assert_eq!(
+            error_and_context,
+            ErrorAndContext::new_owned(file_contents.as_str(), &error)
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_after_yaml_directive() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "%YAML 1.2\n---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.find("field_1").unwrap())),
+                Some((4, 3)),
+                "outer: missing field `field_2`",
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.error_message.as_str(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_after_yaml_and_tag_directives() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+        }
+
+        let file_contents =
+            "%YAML 1.1\n%TAG !e! tag:example.com,2000:\n---\nouter:\n  field_1: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.find("abc").unwrap())),
+                Some((5, 12)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_after_unicode_line_separator() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "# comment\u{2028}field_1: 1\nfield_2: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error_and_context.points_at(file_contents, "abc"), "{error}");
+        assert_eq!(
+            Some((3, 10)),
+            error_and_context.error_line_column,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_crlf_line_endings() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\r\nouter:\r\n  field_1: 123\r\n  field_2: abc\r\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "outer.field_2: invalid type: string \"abc\", expected u32 at line 4 column 12",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from(file_contents.find("abc").unwrap())),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from(
+                    "outer.field_2: invalid type: string \"abc\", expected u32"
+                ),
+                path: Some("outer.field_2".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((4, 12)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((40, 4, 12)),
+                key_span: Some(SourceOffset::from(31)),
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_for_cr_line_endings() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "field_1: 123\rfield_2: abc\r";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "field_2: invalid type: string \"abc\", expected u32 at line 2 column 10",
+            error.to_string()
+        );
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find("abc").unwrap())),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_with_tab_width() {
+        let file_contents = "---\n\t# comment\nouter:\n\t\tfield_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 4 column 9");
+
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find(": 123").unwrap() - 1)),
+            error_and_context.error_span,
+            "{error}"
+        );
+
+        let error_and_context = ErrorAndContext::builder()
+            .tab_width(4)
+            .build(file_contents, &error);
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find("field_1").unwrap())),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_source_offsets_after_multibyte_characters() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "---\n# café ☕\n{\"ünïcödé 😀\": 1, value: abc}\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "value: invalid type: string \"abc\", expected u32 at line 3 column 25",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from(file_contents.find("abc").unwrap())),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from(
+                    "value: invalid type: string \"abc\", expected u32"
+                ),
+                path: Some("value".to_string()),
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((3, 25)),
+                context_line_column: None,
+                source: None,
+                reported_location: Some((47, 3, 25)),
+                key_span: None,
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn expected_returns_variants() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        enum Inner {
+            One,
+            Two,
+            Three,
+        }
+
+        let file_contents = "inner: ~\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            vec!["One".to_string(), "Two".to_string(), "Three".to_string()],
+            error_and_context.expected(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn kind_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::MissingField,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn kind_unknown_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "nam: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::UnknownField,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_from_bytes() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            value: u32,
+        }
+
+        let bytes = "outer:\n  value: abc\n".as_bytes();
+        let error = serde_yaml::from_slice::<Config>(bytes).unwrap_err();
+        let error_and_context = ErrorAndContext::new_from_bytes(bytes, &error);
+
+        assert_eq!(
+            Some(SourceOffset::from(16)),
+            error_and_context.error_span,
+            "{error}"
+        );
+        assert_eq!(
+            Some((2, 10)),
+            error_and_context.error_line_column,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_from_bytes_lossy() {
+        let bytes = b"# \xff\nvalue: abc\n";
+        let error = serde_yaml::Error::custom("invalid value at line 2 column 8");
+        let error_and_context = ErrorAndContext::new_from_bytes(bytes, &error);
+
+        // `\xff` is decoded as the 3 byte `U+FFFD`, so the offset is 2 more
+        // than in `bytes`.
+        assert_eq!(
+            Some(SourceOffset::from(13)),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_from_bytes_with_encoding_latin1() {
+        let bytes = b"caf\xe9: abc\n";
+        let file_contents = bytes.iter().copied().map(char::from).collect::<String>();
+        let error = serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(&file_contents)
+            .unwrap_err();
+        let error_and_context =
+            ErrorAndContext::new_from_bytes_with_encoding(bytes, Encoding::Latin1, &error);
+
+        let error_span = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            b"a",
+            &bytes[error_span..error_span + error_span_len],
+            "{error}"
+        );
+        assert_eq!(Some((1, 7)), error_and_context.error_line_column());
+    }
+
+    #[test]
+    fn unknown_field_name_containing_at() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "name: 1\nlook at line 1 column 2 at line 1 column 1: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "unknown field `look at line 1 column 2 at line 1 column 1`, expected `name`",
+            error_and_context.error_message,
+            "{error}"
+        );
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find("look").unwrap())),
+            error_and_context.error_span,
+            "{error}"
+        );
+        assert_eq!(Some((2, 1)), error_and_context.error_line_column, "{error}");
+    }
+
+    #[test]
+    fn suggestion_for_typo() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "nam: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("name")),
+            error_and_context.suggestion(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn suggestion_none_when_no_close_match() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "colour: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.suggestion(), "{error}");
+    }
+
+    #[test]
+    fn severity_warning_for_unknown_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "name: 1\nnmae: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Severity::Warning, error_and_context.severity(), "{error}");
+        assert_eq!(
+            Some(Severity::Warning),
+            Diagnostic::severity(&error_and_context),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn severity_error_for_syntax_error() {
+        let file_contents = "key: [1, 2\nother: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Severity::Error, error_and_context.severity(), "{error}");
+        assert_eq!(
+            Some(Severity::Error),
+            Diagnostic::severity(&error_and_context),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("add `field_2:` to the mapping")),
+            error_and_context.help(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_unknown_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        #[serde(deny_unknown_fields)]
+        struct Config {
+            name: u32,
+        }
+
+        let file_contents = "nam: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("did you mean `name`?")),
+            error_and_context.help(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_unknown_variant() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            choice: Choice,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        enum Choice {
+            One,
+            Two,
+        }
+
+        let file_contents = "choice: Three\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("expected one of `One`, `Two`")),
+            error_and_context.help(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_invalid_type() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("expected u32")),
+            error_and_context.help(),
+            "{error}"
+        );
+        assert_eq!(
+            Some(String::from("expected u32")),
+            Diagnostic::help(&error_and_context).map(|help| help.to_string()),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_duplicate_key() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: 1\nvalue: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("remove one of the `value` keys")),
+            error_and_context.help(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn help_none_for_other() {
+        let file_contents = "key: [1, 2\nother: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.help(), "{error}");
+        assert!(Diagnostic::help(&error_and_context).is_none(), "{error}");
+    }
+
+    #[test]
+    fn kind_unknown_variant() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        enum Inner {
+            One,
+            Two,
+        }
+
+        let file_contents = "outer:\n  inner: Three\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::UnknownVariant,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn kind_invalid_type() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::InvalidType,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn path_segments_with_sequence_index() {
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            items: Vec<Item>,
+        }
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Item {
+            name: u32,
+        }
+
+        let file_contents = "items:\n- name: 1\n- name: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            vec![
+                PathSegment::Key("items".to_string()),
+                PathSegment::Index(1),
+                PathSegment::Key("name".to_string()),
+            ],
+            error_and_context.path_segments(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn missing_field_name() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("field_2")),
+            error_and_context.missing_field(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn missing_field_name_for_flattened_struct() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            #[serde(flatten)]
+            inner: Inner,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Inner {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(String::from("field_2")),
+            error_and_context.missing_field(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn missing_field_none_for_other_errors() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.missing_field(), "{error}");
+    }
+
+    #[test]
+    fn found_value_from_invalid_type() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: i64,
+        }
+
+        let file_contents = "value: \"say \\\"hi\\\"\"\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some("say \"hi\"".to_string()),
+            error_and_context.found_value(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn kind_duplicate_key() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: 1\nvalue: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::DuplicateKey,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn tab_indentation_error_span_at_tab() {
+        let file_contents = "a: 1\n\tb: 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::TabIndentation,
+            error_and_context.kind(),
+            "{error}"
+        );
+        assert_eq!(
+            (Some(SourceOffset::from(5)), Some(1)),
+            (
+                error_and_context.error_span,
+                error_and_context.error_span_len
+            ),
+            "{error}"
+        );
+        assert_eq!(
+            Some("indent with spaces instead of tabs"),
+            error_and_context.help().as_deref()
+        );
+    }
+
+    #[test]
+    fn tab_indentation_error_span_at_tab_after_spaces() {
+        let file_contents = "a:\n  b: 1\n  \tc: 2\n";
+        let error = serde_yaml::Error::custom(
+            "found a tab character that violates indentation at line 3 column 1, while \
+            scanning a plain scalar at line 2 column 6",
+        );
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Some(SourceOffset::from(12)), error_and_context.error_span);
+        assert_eq!(Some((3, 3)), error_and_context.error_line_column());
+    }
+
+    #[test]
+    fn kind_duplicate_key_for_mapping() {
+        let file_contents = "value: 1\nvalue: 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Mapping>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::DuplicateKey,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn duplicate_key_spans_point_at_both_keys() {
+        let file_contents = "outer:\n  a: 1\n  b: 2\n  a: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Mapping>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(file_contents, 4, 3)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: duplicate entry with key \"a\""),
+                path: Some("outer".to_string()),
+                context_span: Some(SourceOffset::from_location(file_contents, 2, 3)),
+                context_span_len: Some(1),
+                context_message: Some("while parsing this value".to_string()),
+                source_name: None,
+                error_line_column: Some((4, 3)),
+                context_line_column: Some((2, 3)),
+                source: None,
+                reported_location: Some((9, 2, 3)),
+                key_span: Some(SourceOffset::from(0)),
+                context_spans: vec![SourceOffset::from_location(file_contents, 2, 3)],
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn duplicate_field_spans_point_at_both_keys() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: 1\nvalue: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+                Some(SourceOffset::from_location(file_contents, 1, 1)),
+            ),
+            (error_and_context.error_span, error_and_context.context_span),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn while_parsing_block_mapping_error_span_at_problem() {
+        let file_contents = "x: 1\nouter:\n  a: 1\n b: 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 4, 2)),
+                "did not find expected key, while parsing a block mapping",
+                None,
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_message.as_str(),
+                error_and_context.context_span,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn did_not_find_expected_key_context_span_at_mapping_start() {
+        let file_contents = "outer:\n  inner:\n    a: 1\n   b: 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 4, 4)),
+                Some((4, 4)),
+                "did not find expected key, while parsing a block mapping",
+                Some(SourceOffset::from_location(file_contents, 2, 3)),
+                Some((2, 3)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.error_message.as_str(),
+                error_and_context.context_span,
+                error_and_context.context_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn while_parsing_flow_sequence_context_span_at_sequence() {
+        let file_contents = "key: [1, 2\nother: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 2, 6)),
+                Some((2, 6)),
+                Some(SourceOffset::from_location(file_contents, 1, 6)),
+                Some((1, 6)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column(),
+                error_and_context.context_span,
+                error_and_context.context_line_column(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_type_map_error_span_covers_block() {
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            #[allow(dead_code)]
+            name: String,
+            #[allow(dead_code)]
+            other: u32,
+        }
+
+        let file_contents = "name:\n  first: a\n  last: b\nother: 1\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_span = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            "first: a\n  last: b",
+            &file_contents[error_span..error_span + error_span_len],
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn block_scalar_error_span_points_into_body() {
+        struct Never;
+
+        impl<'de> Deserialize<'de> for Never {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct NeverVisitor;
+
+                impl serde::de::Visitor<'_> for NeverVisitor {
+                    type Value = Never;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str("never")
+                    }
+
+                    fn visit_str<E>(self, _value: &str) -> Result<Never, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        Err(E::custom("text is never valid"))
+                    }
+                }
+
+                deserializer.deserialize_str(NeverVisitor)
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Config {
+            #[allow(dead_code)]
+            text: Never,
+        }
+
+        let file_contents = "a: 1\ntext: |\n  a\tb\n  \tc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).err().unwrap();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some((3, 3)),
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column(),
+                error_and_context.key_span(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn byte_order_mark_error_span_on_first_line() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            a: u32,
+        }
+
+        let file_contents = "\u{feff}a: x\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let error_range = error_and_context.error_range().unwrap();
+        assert_eq!("x", &file_contents[error_range], "{error}");
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find('a').unwrap())),
+            error_and_context.key_span(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn unknown_anchor_error_span_points_at_alias() {
+        let file_contents = "a: &x 1\nb: *y\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 2, 4)),
+                Some(1),
+                "unknown anchor",
+                None,
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_span_len,
+                error_and_context.error_message.as_str(),
+                error_and_context.context_span,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn unknown_anchor_context_span_points_at_later_anchor() {
+        let file_contents = "a: *x\nb: &y 1\nc: &x 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 1, 4)),
+                Some(SourceOffset::from_location(file_contents, 3, 4)),
+                Some((3, 4)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.context_span,
+                error_and_context.context_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_for_document_error_span_in_second_document() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            outer: Inner,
+        }
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Inner {
+            a: u32,
+            b: u32,
+        }
+
+        let file_contents = "---\nouter:\n  a: 1\n  b: 2\n---\nouter:\n  a: 1\n";
+        let error = serde_yaml::Deserializer::from_str(file_contents)
+            .map(Outer::deserialize)
+            .find_map(Result::err)
+            .unwrap();
+        let error_and_context = ErrorAndContext::new_for_document(file_contents, 1, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 7, 3)),
+                Some((7, 3)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_for_document_offsets_error_from_document_alone() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            a: u32,
+            b: u32,
+        }
+
+        let file_contents = "a: 1\nb: 2\nc: 3\n---\na: 1\nb: x\n";
+        let error = serde_yaml::from_str::<Config>("---\na: 1\nb: x\n").unwrap_err();
+        let error_and_context = ErrorAndContext::new_for_document(file_contents, 1, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 6, 4)),
+                Some((6, 4)),
+                Some(SourceOffset::from_location(file_contents, 6, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column(),
+                error_and_context.key_span(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn end_of_input_context_span_at_document_start() {
+        let file_contents = "---\nouter:\n  a: [1,\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.len())),
+                Some(SourceOffset::from(0)),
+                Some((1, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.context_span,
+                error_and_context.context_line_column(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn end_of_input_context_span_skips_leading_comments() {
+        let file_contents = "# config\nouter:\n  a: [1,\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(SourceOffset::from_location(file_contents, 2, 1)),
+            error_and_context.context_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_type_for_empty_last_value_has_no_context_span() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            b: Vec<u32>,
+            a: u32,
+        }
+
+        let file_contents = "b: []\na:\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "a: invalid type: unit value, expected u32", error_and_context.error_message,
+            "{error}"
+        );
+        assert_eq!(None, error_and_context.context_span, "{error}");
+    }
+
+    #[test]
+    fn position_clamped_to_end_of_file_has_no_context_span() {
+        let file_contents = "value: 1\n";
+        let error = serde_yaml::Error::custom("x at position 999");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (Some(SourceOffset::from(file_contents.len())), None),
+            (error_and_context.error_span, error_and_context.context_span)
+        );
+    }
+
+    #[test]
+    fn quoted_unexpected_end_in_value_has_no_context_span() {
+        let file_contents = "value: 1\n";
+        let error = serde_yaml::Error::custom(
+            "invalid value: string \"unexpected end of file\" at position 999",
+        );
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (Some(SourceOffset::from(file_contents.len())), None),
+            (error_and_context.error_span, error_and_context.context_span)
+        );
+    }
+
+    #[test]
+    fn offsets_clamped_for_unexpected_end_of_input() {
+        let file_contents = "value: \"abc\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.len())),
+            error_and_context.error_span,
+            "{error}"
+        );
+
+        let mut rendered = String::new();
+        let report = error_and_context.into_report("config.yaml", file_contents);
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+    }
+
+    #[test]
+    fn offsets_clamped_when_past_end_of_file() {
+        let file_contents = "value: 1\n";
+        let error = serde_yaml::Error::custom("invalid value at line 5 column 1 at position 99");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.len())),
+                Some(SourceOffset::from(file_contents.len())),
+            ),
+            (error_and_context.error_span, error_and_context.context_span)
+        );
+    }
+
+    #[test]
+    fn malformed_marks_have_no_span() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        [
+            "invalid value at line  column ",
+            "invalid value at line column 3",
+            "invalid value at line 3 column",
+            "invalid value at position ",
+            "invalid value at line -1 column 3",
+        ]
+        .into_iter()
+        .for_each(|error_string| {
+            let error = serde_yaml::Error::custom(error_string);
+            let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+            assert_eq!(None, error_and_context.error_span, "{error}");
+            assert_eq!(None, error_and_context.context_span, "{error}");
+        });
+    }
+
+    #[test]
+    fn overflowing_marks_are_clamped() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        [
+            "invalid value at line 999999999999999999999 column 1",
+            "invalid value at line 1 column 999999999999999999999",
+            "invalid value at line 18446744073709551615 column 18446744073709551615",
+            "invalid value at position 999999999999999999999",
+        ]
+        .into_iter()
+        .zip([
+            file_contents.len(),
+            3,
+            file_contents.len(),
+            file_contents.len(),
+        ])
+        .for_each(|(error_string, offset_expected)| {
+            let error = serde_yaml::Error::custom(error_string);
+            let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+            assert_eq!(
+                Some(SourceOffset::from(offset_expected)),
+                error_and_context.error_span,
+                "{error}"
+            );
+            assert_eq!("invalid value", error_and_context.error_message, "{error}");
+        });
+    }
+
+    #[test]
+    fn recursion_limit_span_at_reported_location() {
+        let file_contents = format!("outer:\n  inner: {}", "[".repeat(200));
+        let error = serde_yaml::from_str::<serde_yaml::Value>(&file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(&file_contents, &error);
+
+        assert!(error_and_context.is_recursion_limit(), "{error}");
+        assert_eq!("recursion limit exceeded", error_and_context.error_message);
+        assert_eq!(
+            Some(SourceOffset::from_location(&file_contents, 2, 136)),
+            error_and_context.error_span,
+            "{error}"
+        );
+        assert_eq!(None, error_and_context.context_span);
+    }
+
+    #[test]
+    fn recursion_limit_without_location_has_no_span() {
+        let file_contents = "value: 1\n";
+        let error = serde_yaml::Error::custom("recursion limit exceeded");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error_and_context.is_recursion_limit());
+        assert_eq!(None, error_and_context.error_span);
+        assert_eq!(None, error_and_context.context_span);
+    }
+
+    #[test]
+    fn reported_location_differs_from_error_span() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: 1\nvalue: 2\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Some((0, 1, 1)), error_and_context.reported_location());
+        assert_eq!(
+            Some((2, 1)),
+            error_and_context.error_line_column(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn reported_location_none_without_location() {
+        let file_contents = "value: 1\n";
+        let error = serde_yaml::Error::custom("invalid value at line 1 column 8");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.reported_location());
+    }
+
+    #[test]
+    fn kind_other() {
+        let file_contents = "value: [1, 2\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            YamlErrorKind::Other(error_and_context.error_message.to_string()),
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn path_for_nested_value() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            items: [Item; 2],
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Item {
+            value: u32,
+        }
+
+        let file_contents = "outer:\n  items:\n  - value: 1\n  - value: 'a: b'\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some("outer.items[1].value"),
+            error_and_context.path.as_deref(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn path_none_for_custom_error_with_colon() {
+        let file_contents = "---\nerror: 123\n";
+        let error = serde_yaml::Error::custom("error: x");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.path);
+        assert_eq!("error: x", error_and_context.error_message);
+    }
+
+    #[test]
+    fn path_none_for_root_value() {
+        let file_contents = "abc\n";
+        let error = serde_yaml::from_str::<u32>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "invalid type: string \"abc\", expected u32",
+            error.to_string()
+        );
+        assert_eq!(None, error_and_context.path, "{error}");
+    }
+
+    #[test]
+    fn builder_with_custom_delimiter() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value @ line 3 column 3 @ line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .delimiter(" @ ")
+            .build(file_contents, &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(file_contents, 3, 3)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("invalid value"),
+                path: None,
+                context_span: Some(SourceOffset::from_location(file_contents, 2, 1)),
+                context_span_len: Some(1),
+                context_message: Some("while parsing this value".to_string()),
+                source_name: None,
+                error_line_column: Some((3, 3)),
+                context_line_column: Some((2, 1)),
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: vec![SourceOffset::from_location(file_contents, 2, 1)],
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_strip_ansi() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom(
+            "\x1b[31mouter: invalid value\x1b[0m at line \x1b[1m3\x1b[0m column 3",
+        );
+        let error_and_context = ErrorAndContext::builder()
+            .strip_ansi(true)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                "outer: invalid value",
+                Some("outer"),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_message.as_str(),
+                error_and_context.path.as_deref(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn error_span_at_mark_before_comma() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom(
+            "outer.field_1: invalid value at line 3 column 12, expected a string",
+        );
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 12)),
+                Some((3, 12)),
+                "outer.field_1: invalid value, expected a string",
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.error_message.as_str(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_mark_order_error_then_context() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .mark_order(MarkOrder::ErrorThenContext)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+            ),
+            (error_and_context.error_span, error_and_context.context_span),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_mark_order_context_then_error() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 2 column 1 at line 3 column 3");
+        let error_and_context = ErrorAndContext::builder()
+            .mark_order(MarkOrder::ContextThenError)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some((3, 3)),
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+                Some((2, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.context_span,
+                error_and_context.context_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_mark_order_error_then_context_agrees_with_auto() {
+        let file_contents = "key: [1, 2\nother: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .mark_order(MarkOrder::ErrorThenContext)
+            .build(file_contents, &error);
+
+        assert_ne!(Some(0), error.location().map(|location| location.index()));
+        assert_eq!(
+            ErrorAndContext::new(file_contents, &error),
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_mark_order_context_then_error_for_while_parsing() {
+        let file_contents = "key: [1, 2\nother: 3\n";
+        let error = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .mark_order(MarkOrder::ContextThenError)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 1, 6)),
+                Some(SourceOffset::from_location(file_contents, 2, 6)),
+            ),
+            (error_and_context.error_span, error_and_context.context_span),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_snap_to_token_start() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 5");
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .snap_to_token_start(true)
+            .build(file_contents, &error);
+
+        assert!(
+            error_and_context.points_at(file_contents, "field_1"),
+            "{error}"
+        );
+        assert_eq!(
+            (Some((3, 3)), Some(7)),
+            (
+                error_and_context.error_line_column,
+                error_and_context.error_span_len
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn snap_to_token_start_off_by_default() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 5");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(
+            error_and_context.points_at(file_contents, "eld_1"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_max_message_len_truncates_with_ellipsis() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        enum Level {
+            Trace,
+            Debug,
+            Info,
+            Warn,
+            Error,
+        }
+
+        let file_contents = "Verbose\n";
+        let error = serde_yaml::from_str::<Level>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .max_message_len(40)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            "unknown variant `Verbose`, expected one…", error_and_context.error_message,
+            "{error}"
+        );
+        assert_eq!(40, error_and_context.error_message.chars().count());
+        assert_eq!(
+            YamlErrorKind::UnknownVariant,
+            error_and_context.kind(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn column_without_line_is_on_first_line() {
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::Error::custom("invalid value at column 8");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (Some(SourceOffset::from(7)), Some((1, 8)), "invalid value"),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.error_message.as_str(),
+            )
+        );
+    }
+
+    #[test]
+    fn flow_mapping_missing_field_span_covers_mapping() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter: { field_1: 123 }\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_span = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            (
+                "{ field_1: 123 }",
+                Some((2, 8)),
+                Some(SourceOffset::from(4))
+            ),
+            (
+                &file_contents[error_span..error_span + error_span_len],
+                error_and_context.error_line_column,
+                error_and_context.key_span,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn flow_mapping_invalid_type_span_at_value() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "{outer: {field_1: 1, field_2: abc}}\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let error_span = error_and_context.error_span.unwrap().offset();
+        let error_span_len = error_and_context.error_span_len.unwrap();
+        assert_eq!(
+            ("abc", Some((1, 31))),
+            (
+                &file_contents[error_span..error_span + error_span_len],
+                error_and_context.error_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_column_base_zero_based() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 2 at line 2 column 0");
+        let error_and_context = ErrorAndContext::builder()
+            .column_base(ColumnBase::ZeroBased)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some((3, 3)),
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+                Some((2, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.context_span,
+                error_and_context.context_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn new_with_extractor_uses_extracted_marks() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("outer: invalid value (3:3)");
+        let error_and_context =
+            ErrorAndContext::new_with_extractor(file_contents, &error, |error_string| {
+                assert_eq!("outer: invalid value (3:3)", error_string);
+                vec![(3, 3)]
+            });
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some((3, 3)),
+                "outer: invalid value (3:3)",
+                Some("outer"),
+                None,
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_line_column,
+                error_and_context.error_message.as_str(),
+                error_and_context.path.as_deref(),
+                error_and_context.context_span,
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn empty_file_contents_has_no_spans() {
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new("", &error);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: None,
+                error_span_len: None,
+                error_message: ErrorMessage::from("invalid value"),
+                path: None,
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: None,
+                context_line_column: None,
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+        assert_eq!(None, error_and_context.render_caret(""));
+    }
+
+    #[test]
+    fn empty_file_contents_renders_message() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let error = serde_yaml::from_str::<Config>("").unwrap_err();
+        let report = ErrorAndContext::new("", &error).into_report("config.yaml", "");
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            "\n  × missing field `value`\n  help: add `value:` to the mapping\n",
+            rendered
+        );
+    }
+
+    #[test]
+    fn new_with_pattern_translated() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom(
+            "outer: valor inválido en la línea 3 columna 3 en la línea 2 columna 1",
+        );
+        let location_pattern = LocationPattern {
+            at_line: String::from(" en la línea "),
+            column: String::from(" columna "),
+            at_position: String::from(" en la posición "),
+        };
+        let error_and_context =
+            ErrorAndContext::new_with_pattern(file_contents, &error, &location_pattern);
+
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from_location(file_contents, 3, 3)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("outer: valor inválido"),
+                path: Some("outer".to_string()),
+                context_span: Some(SourceOffset::from_location(file_contents, 2, 1)),
+                context_span_len: Some(1),
+                context_message: Some("while parsing this value".to_string()),
+                source_name: None,
+                error_line_column: Some((3, 3)),
+                context_line_column: Some((2, 1)),
+                source: None,
+                reported_location: None,
+                key_span: Some(SourceOffset::from(4)),
+                context_spans: vec![SourceOffset::from_location(file_contents, 2, 1)],
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn builder_with_source_name() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::builder()
+            .source_name("config.yaml")
+            .build(file_contents, &error);
+
+        assert_eq!(
+            Some("config.yaml"),
+            error_and_context.source_name.as_deref(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn with_source_name_renders_in_report() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context =
+            ErrorAndContext::new_owned(file_contents, &error).with_source_name("config.yaml");
+        let report = miette::Report::new(error_and_context);
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            r#"
+  × value: invalid type: string "abc", expected u32
+   ╭─[config.yaml:1:8]
+ 1 │ value: abc
+   ·        ┬
+   ·        ╰── value: invalid type: string "abc", expected u32
+   ╰────
+  help: expected u32
+"#,
+            rendered
+        );
+    }
+
+    #[test]
+    fn into_parts_returns_spans_and_message() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let (error_span, error_message, context_span) = error_and_context.clone().into_parts();
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                "invalid value",
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+            ),
+            (error_span, error_message.as_str(), context_span)
+        );
+        assert_eq!(
+            error_and_context,
+            ErrorAndContext {
+                error_span,
+                error_message: ErrorMessage::from(error_message),
+                context_span,
+                ..error_and_context.clone()
+            }
+        );
+    }
+
+    #[test]
+    fn points_at_needle_at_error_span() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n  field_2: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error_and_context.points_at(file_contents, "abc"), "{error}");
+        assert!(
+            !error_and_context.points_at(file_contents, "field_2"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn points_at_false_without_error_span() {
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new("", &error);
+
+        assert!(!error_and_context.points_at("", ""));
+    }
+
+    #[test]
+    fn new_has_no_source_name() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+
+        assert_eq!(
+            None,
+            ErrorAndContext::new(file_contents, &error).source_name
+        );
+    }
+
+    #[cfg(feature = "ariadne")]
+    #[test]
+    fn to_ariadne_report_labels_error_and_context() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        let mut rendered = Vec::new();
+        error_and_context
+            .to_ariadne_report("config.yaml")
+            .write(
+                ("config.yaml", ariadne::Source::from(file_contents)),
+                &mut rendered,
+            )
+            .unwrap();
+        let rendered = crate::message::strip_ansi(&String::from_utf8(rendered).unwrap());
+
+        assert_eq!(
+            vec![
+                "Error: invalid value",
+                "   ╭─[ config.yaml:3:3 ]",
+                "   │",
+                " 3 │   field_1: 123",
+                "   │   ───┬───",
+                "   │      ╰───── invalid value",
+                "   │",
+                "   ├─[ config.yaml:3:3 ]",
+                "   │",
+                " 2 │ outer:",
+                "   │ ──┬──",
+                "   │   ╰──── while parsing this value",
+                "───╯",
+            ],
+            rendered.lines().map(str::trim_end).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn from_spanned_serde_yaml() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::from_spanned(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(7)),
+                Some(1),
+                r#"value: invalid type: string "abc", expected u32"#,
+                Some("value"),
+                Some((1, 8)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_span_len,
+                error_and_context.error_message.as_str(),
+                error_and_context.path.as_deref(),
+                error_and_context.error_line_column,
+            ),
+            "{error}"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn from_spanned_toml() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+        }
+
+        let file_contents = "[outer]\nfield_1 = \"abc\"\n";
+        let error = toml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::from_spanned(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.find('"').unwrap())),
+                Some(5),
+                r#"invalid type: string "abc", expected u32"#,
+                Some((2, 11)),
+                None,
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_span_len,
+                error_and_context.error_message.as_str(),
+                error_and_context.error_line_column,
+                error_and_context.context_span,
+            ),
+            "{error}"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_json_error_returns_source_offsets() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = r#"{
+  "outer": {
+    "field_1": 123,
+    "field_2": "abc"
+  }
+}"#;
+        let error = serde_json::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::from_json_error(file_contents, &error);
+
+        assert_eq!(
+            "invalid type: string \"abc\", expected u32 at line 4 column 20",
+            error.to_string()
+        );
+        assert_eq!(
+            ErrorAndContext {
+                error_span: Some(SourceOffset::from(file_contents.find("c\"").unwrap() + 1)),
+                error_span_len: Some(1),
+                error_message: ErrorMessage::from("invalid type: string \"abc\", expected u32"),
+                path: None,
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: Some((4, 20)),
+                context_line_column: None,
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_json_error_counts_columns_in_bytes() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = r#"{ "ключ": 1, "value": true }"#;
+        let error = serde_json::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::from_json_error(file_contents, &error);
+
+        assert_eq!(
+            "invalid type: boolean `true`, expected u32 at line 1 column 30",
+            error.to_string()
+        );
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.find("true").unwrap() + 3)),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_json_error_without_location() {
+        let file_contents = "{}";
+        let error = <serde_json::Error as serde::de::Error>::custom("invalid value");
+        let error_and_context = ErrorAndContext::from_json_error(file_contents, &error);
+
+        assert_eq!(None, error_and_context.error_span, "{error}");
+        assert_eq!("invalid value", error_and_context.error_message, "{error}");
+    }
+
+    #[test]
+    fn named_source_has_name_and_contents() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let named_source = ErrorAndContext::named_source(file_contents, "config.yaml");
+
+        assert_eq!("config.yaml", named_source.name());
+        assert_eq!(file_contents, named_source.inner());
+    }
+
+    #[test]
+    fn error_message_preserves_delimiter_in_value() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: meet at noon\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "value: invalid type: string \"meet at noon\", expected u32 at line 1 column 8",
+            error.to_string()
+        );
+        assert_eq!(
+            "value: invalid type: string \"meet at noon\", expected u32",
+            error_and_context.error_message,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn returns_no_source_offsets_when_error_has_no_location() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+        struct Config;
+
+        impl<'de> Deserialize<'de> for Config {
+            fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                Err(serde::de::Error::custom("config is never valid"))
+            }
+        }
+
+        let file_contents = "value: 1\n";
+        let value = serde_yaml::from_str::<serde_yaml::Value>(file_contents).unwrap();
+        let error = serde_yaml::from_value::<Config>(value).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error.location().is_none());
+        assert!(!error_and_context.has_location());
+        assert_eq!(
+            ErrorAndContext {
+                error_span: None,
+                error_span_len: None,
+                error_message: ErrorMessage::from("config is never valid"),
+                path: None,
+                context_span: None,
+                context_span_len: None,
+                context_message: None,
+                source_name: None,
+                error_line_column: None,
+                context_line_column: None,
+                source: None,
+                reported_location: None,
+                key_span: None,
+                context_spans: Vec::new(),
+            },
+            error_and_context,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn has_location_when_error_span_is_found() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert!(error_and_context.has_location());
+    }
+
+    #[test]
+    fn returns_source_offsets_for_large_document() {
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            items: Vec<Item>,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Item {
+            value: u32,
+        }
+
+        let file_contents = (0..5000)
+            .map(|n| format!("- value: {n}\n"))
+            .chain(std::iter::once(String::from("- value: abc\n")))
+            .fold(String::from("---\nitems:\n"), |mut file_contents, item| {
+                file_contents.push_str(&item);
+                file_contents
+            });
+        let error = serde_yaml::from_str::<Config>(&file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(&file_contents, &error);
+
+        assert_eq!(
+            "items[5000].value: invalid type: string \"abc\", expected u32 at line 5003 column 10",
+            error.to_string()
+        );
+        assert_eq!(
+            Some(SourceOffset::from(file_contents.rfind("abc").unwrap())),
+            error_and_context.error_span,
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn error_line_column_for_missing_field() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            outer: Outer,
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Outer {
+            field_1: u32,
+            field_2: u32,
+        }
+
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some((3, 3)),
+            error_and_context.error_line_column(),
+            "{error}"
+        );
+        assert_eq!(None, error_and_context.context_line_column(), "{error}");
+    }
+
+    #[test]
+    fn context_line_column_for_two_marks() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some((3, 3)),
+            error_and_context.error_line_column(),
+            "{error}"
+        );
+        assert_eq!(
+            Some((2, 1)),
+            error_and_context.context_line_column(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn context_message_default_when_context_span_present() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some("while parsing this value"),
+            error_and_context.context_message.as_deref(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn context_message_none_when_context_span_absent() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::builder()
+            .context_message("within this mapping")
+            .build(file_contents, &error);
+
+        assert_eq!(None, error_and_context.context_span, "{error}");
+        assert_eq!(None, error_and_context.context_message, "{error}");
+    }
+
+    #[test]
+    fn context_message_from_builder() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .context_message("within this mapping")
+            .build(file_contents, &error);
+
+        assert_eq!(
+            Some("within this mapping"),
+            error_and_context.context_message.as_deref(),
+            "{error}"
+        );
+        assert_eq!(
+            Some(vec![
+                LabeledSpan::new_primary_with_span(
+                    Some("invalid value".to_string()),
+                    SourceSpan::new(SourceOffset::from(13), 1),
+                ),
+                LabeledSpan::new_with_span(
+                    Some("within this mapping".to_string()),
+                    SourceSpan::new(SourceOffset::from(4), 1),
+                ),
+            ]),
+            Diagnostic::labels(&error_and_context).map(Iterator::collect::<Vec<_>>)
+        );
+    }
+
+    #[test]
+    fn from_file_contents_and_error() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: abc\n";
+        let error_and_context: ErrorAndContext = serde_yaml::from_str::<Config>(file_contents)
+            .map_err(|error| -> ErrorAndContext { (file_contents, &error).into() })
+            .unwrap_err();
+
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        assert_eq!(
+            ErrorAndContext::new(file_contents, &error),
+            error_and_context
+        );
+    }
+
+    #[test]
+    fn invalid_type_info_for_string() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: u32,
+        }
+
+        let file_contents = "value: 'a, expected b'\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(("string \"a, expected b\"".to_string(), "u32".to_string())),
+            error_and_context.invalid_type_info(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn invalid_type_info_for_sequence() {
+        #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+        struct Config {
+            value: String,
+        }
+
+        let file_contents = "value: [1, 2]\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            Some(("sequence".to_string(), "a string".to_string())),
+            error_and_context.invalid_type_info(),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn enclosing_span_brackets_error_and_context() {
+        let file_contents = r#"---
+outer:
+  # inner
+  inner_outer:
+    # inner_inner
+    field_1: 123
+"#;
+        let error = serde_yaml::Error::custom(
+            "outer: missing field `field_2` at line 6 column 5 at line 4 column 3",
+        );
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        let (start, len) = error_and_context.enclosing_span().unwrap();
+        assert_eq!(
+            "inner_outer:\n    # inner_inner\n    ",
+            &file_contents[start..start + len]
+        );
+    }
+
+    #[test]
+    fn context_spans_innermost_to_outermost() {
+        let file_contents = r#"---
+outer:
+  # inner
+  inner_outer:
+    # inner_inner
+    field_1: 123
+"#;
+        let error = serde_yaml::Error::custom(
+            "outer: missing field `field_2` at line 6 column 5 at line 4 column 3 at line 2 column 1",
+        );
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 6, 5)),
+                Some(SourceOffset::from_location(file_contents, 4, 3)),
+                Some((4, 3)),
+                &[
+                    SourceOffset::from_location(file_contents, 4, 3),
+                    SourceOffset::from_location(file_contents, 2, 1),
+                ][..],
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.context_span,
+                error_and_context.context_line_column(),
+                error_and_context.context_spans(),
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn enclosing_span_when_context_after_error() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 2 column 1 at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(Some((4, 9)), error_and_context.enclosing_span());
+    }
+
+    #[test]
+    fn enclosing_span_none_without_context() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(None, error_and_context.enclosing_span());
+    }
+
+    #[test]
+    fn zero_index_location_uses_marks_in_string() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error_and_context = ErrorAndContext::from_display(
+            file_contents,
+            "outer: missing field `field_2` at line 3 column 3 at line 2 column 1",
+            Some((0, 4, 7)),
+        );
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                Some(SourceOffset::from_location(file_contents, 2, 1)),
+            ),
+            (error_and_context.error_span, error_and_context.context_span)
+        );
+    }
+
+    #[test]
+    fn zero_index_location_used_when_string_has_no_marks() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error_and_context =
+            ErrorAndContext::from_display(file_contents, "outer: invalid value", Some((0, 3, 3)));
+
+        assert_eq!(
+            (Some(SourceOffset::from_location(file_contents, 3, 3)), None),
+            (error_and_context.error_span, error_and_context.context_span)
+        );
+    }
+
+    #[test]
+    fn try_from_dyn_downcasts_serde_yaml_error() {
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(file_contents)
+            .unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+        let error: Box<dyn std::error::Error> = Box::new(error);
+
+        assert_eq!(
+            Some(error_and_context),
+            ErrorAndContext::try_from_dyn(file_contents, error.as_ref())
+        );
+    }
 
-        let error_message = error_string
-            .split(" at ")
-            .next()
-            .map(str::to_string)
-            .unwrap_or(error_string);
+    #[test]
+    fn try_from_dyn_none_for_other_errors() {
+        let error: Box<dyn std::error::Error> = Box::new(std::fmt::Error);
 
-        ErrorAndContext {
-            error_span,
-            error_message,
-            context_span,
-        }
+        assert_eq!(None, ErrorAndContext::try_from_dyn("", error.as_ref()));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use miette::SourceOffset;
-    use serde::{Deserialize, Serialize};
+    #[test]
+    fn new_with_location_uses_given_message_and_location() {
+        // `serde_yaml::Location` cannot be constructed directly, so take it from
+        // an error at the same position.
+        let file_contents = "---\nouter:\n  field_1: abc\n";
+        let location = serde_yaml::from_str::<serde_yaml::Value>("---\nouter:\n  field_1: [\n")
+            .unwrap_err()
+            .location();
+        let error_and_context = ErrorAndContext::new_with_location(
+            file_contents,
+            "outer.field_1: invalid value",
+            location,
+        );
 
-    use super::ErrorAndContext;
+        assert_eq!(
+            (
+                Some(SourceOffset::from(file_contents.len())),
+                "outer.field_1: invalid value",
+                Some((24, 4, 1)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_message.as_str(),
+                error_and_context.reported_location(),
+            )
+        );
+    }
 
     #[test]
-    fn returns_source_offsets_for_missing_field() {
+    fn new_with_location_matches_new() {
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
         struct Config {
-            outer: Outer,
-        }
-
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Outer {
-            field_1: u32,
-            field_2: u32,
+            value: u32,
         }
 
-        let file_contents = r#"---
-outer:
-  field_1: 123
-# ^
-# '--- field_2 missing the first character of the first type that has `#[serde(flatten)]`.
-"#;
+        let file_contents = "value: abc\n";
         let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
-        let error_and_context = ErrorAndContext::new(file_contents, &error);
-
-        let loc_line = 3;
-        let loc_col = 3; // index 2 is column 3
 
         assert_eq!(
-            "outer: missing field `field_2` at line 3 column 3",
-            error.to_string()
-        );
-        assert_eq!(
-            ErrorAndContext {
-                error_span: Some(SourceOffset::from_location(
-                    file_contents,
-                    loc_line,
-                    loc_col
-                )),
-                error_message: "outer: missing field `field_2`".to_string(),
-                context_span: None,
-            },
-            error_and_context,
-            "{error}"
+            ErrorAndContext::new(file_contents, &error),
+            ErrorAndContext::new_with_location(file_contents, &error.to_string(), error.location())
         );
     }
 
     #[test]
-    fn returns_source_offsets_for_missing_field_for_flattened_struct() {
+    fn from_display_matches_new() {
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
         struct Config {
-            outer: Outer,
-        }
-
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Outer {
-            #[serde(flatten)]
-            inner: Inner,
+            outer: Inner,
         }
-
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
         struct Inner {
             field_1: u32,
             field_2: u32,
         }
 
-        let file_contents = r#"---
-outer:
-  # inner
-  field_1: 123
-# ^
-# '-- field_2 missing on the first character of the wrapping first type that has `#[serde(flatten)]`.
-"#;
+        let file_contents = "---\nouter:\n  field_1: 123\n";
         let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
-        let error_and_context = ErrorAndContext::new(file_contents, &error);
-
-        let loc_line = 4;
-        let loc_col = 3; // index 2 is column 3
+        let error_and_context = ErrorAndContext::from_display(
+            file_contents,
+            "outer: missing field `field_2` at line 3 column 3",
+            Some((13, 3, 3)),
+        );
 
         assert_eq!(
-            "outer: missing field `field_2` at line 4 column 3",
-            error.to_string()
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                "outer: missing field `field_2`",
+                Some((13, 3, 3)),
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_message.as_str(),
+                error_and_context.reported_location(),
+            )
         );
         assert_eq!(
-            ErrorAndContext {
-                error_span: Some(SourceOffset::from_location(
-                    file_contents,
-                    loc_line,
-                    loc_col
-                )),
-                error_message: "outer: missing field `field_2`".to_string(),
-                context_span: None,
-            },
-            error_and_context,
-            "{error}"
+            ErrorAndContext::new(file_contents, &error),
+            error_and_context
         );
     }
 
     #[test]
-    fn returns_source_offsets_for_missing_field_for_nested_flattened_struct() {
+    fn render_caret_under_error_token() {
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
         struct Config {
-            outer: Outer,
-        }
-
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Outer {
-            #[serde(flatten)]
-            inner: Inner,
+            outer: Inner,
         }
-
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
         struct Inner {
-            #[serde(flatten)]
-            inner_inner: InnerInner,
-        }
-
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct InnerInner {
             field_1: u32,
             field_2: u32,
         }
 
-        let file_contents = r#"---
-outer:
-  # inner, inner_inner
-  field_1: 123
-# ^
-# '-- field_2 missing on the first character of the first type that has `#[serde(flatten)]`.
-"#;
+        let file_contents = "---\nouter:\n  field_1: 123\n";
         let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
+
+        assert_eq!(
+            Some("  field_1: 123\n  ^^^^^^^".to_string()),
+            error_and_context.render_caret(file_contents)
+        );
+    }
+
+    #[test]
+    fn render_caret_keeps_tabs() {
+        let file_contents = "value: [\t\"é\", 2]\n";
+        let error = serde_yaml::Error::custom("invalid value at line 1 column 10");
         let error_and_context = ErrorAndContext::new(file_contents, &error);
 
-        let loc_line = 4;
-        let loc_col = 3; // index 2 is column 3
+        assert_eq!(
+            Some("value: [\t\"é\", 2]\n        \t^".to_string()),
+            error_and_context.render_caret(file_contents)
+        );
+    }
+
+    #[test]
+    fn render_caret_snaps_span_to_char_boundaries() {
+        let file_contents = "a: é€\n";
+        let error = serde_yaml::Error::custom("invalid value at line 1 column 4");
+        let mut error_and_context = ErrorAndContext::new(file_contents, &error);
+        error_and_context.error_span = Some(SourceOffset::from(4));
+        error_and_context.error_span_len = Some(3);
 
         assert_eq!(
-            "outer: missing field `field_2` at line 4 column 3",
-            error.to_string()
+            Some("a: é€\n   ^".to_string()),
+            error_and_context.render_caret(file_contents)
         );
+    }
+
+    #[test]
+    fn render_caret_on_line_after_unicode_line_separator() {
+        let file_contents = "a: 1\u{2028}b: x\n";
+        let error = serde_yaml::Error::custom("invalid value at line 2 column 4");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
         assert_eq!(
-            ErrorAndContext {
-                error_span: Some(SourceOffset::from_location(
-                    file_contents,
-                    loc_line,
-                    loc_col
-                )),
-                error_message: "outer: missing field `field_2`".to_string(),
-                context_span: None,
-            },
-            error_and_context,
-            "{error}"
+            Some("b: x\n   ^".to_string()),
+            error_and_context.render_caret(file_contents)
         );
     }
 
     #[test]
-    fn returns_source_offsets_for_missing_field_for_nested_flattened_struct_2() {
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Config {
-            outer: Outer,
-        }
+    fn render_caret_none_without_location() {
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new("a: 1\n", &error);
 
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Outer {
-            #[serde(flatten)]
-            inner: Inner,
-        }
+        assert_eq!(None, error_and_context.render_caret("a: 1\n"));
+    }
 
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Inner {
-            inner_outer: InnerOuter,
-        }
+    #[test]
+    fn message_eq_ignores_spans() {
+        let error = serde_yaml::Error::custom("outer: invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new("---\nouter:\n  field_1: 123\n", &error);
+        let error_and_context_elsewhere = ErrorAndContext::new("outer: 1\n", &error);
+
+        assert!(error_and_context.message_eq("outer: invalid value"));
+        assert!(error_and_context_elsewhere.message_eq("outer: invalid value"));
+        assert!(!error_and_context.message_eq("outer: invalid value at line 3 column 3"));
+    }
 
+    #[test]
+    fn to_compact_with_line_and_column() {
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct InnerOuter {
-            #[serde(flatten)]
-            inner_inner: InnerInner,
+        struct Config {
+            outer: Inner,
         }
-
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct InnerInner {
+        struct Inner {
             field_1: u32,
             field_2: u32,
         }
 
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
+        assert_eq!(
+            "config.yaml:3:3: outer: missing field `field_2`",
+            error_and_context.to_compact("config.yaml", file_contents)
+        );
+    }
+
+    #[test]
+    fn to_compact_without_location() {
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new("a: 1\n", &error);
+
+        assert_eq!(
+            "config.yaml: invalid value",
+            error_and_context.to_compact("config.yaml", "a: 1\n")
+        );
+    }
+
+    #[test]
+    fn error_range_and_context_range_are_half_open() {
         let file_contents = r#"---
 outer:
   # inner
   inner_outer:
     # inner_inner
     field_1: 123
-# ^
-# '-- field_2 is always marked as missing on the first character of the
-#     first wrapping type. Ideally it would point to `field_1`'s position (line 6 col 5).
 "#;
-        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
-        let error_and_context = ErrorAndContext::new(file_contents, &error);
+        let error = serde_yaml::Error::custom(
+            "outer: missing field `field_2` at line 6 column 5 at line 4 column 3",
+        );
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
 
-        let loc_line = 4;
-        let loc_col = 3; // index 4 is column 3
+        let error_range = error_and_context.error_range().unwrap();
+        let context_range = error_and_context.context_range().unwrap();
+        assert_eq!(58..65, error_range);
+        assert_eq!("field_1", &file_contents[error_range]);
+        assert_eq!(23..34, context_range);
+        assert_eq!("inner_outer", &file_contents[context_range]);
+    }
+
+    #[test]
+    fn to_lsp_range_is_zero_based() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = serde_yaml::Error::custom("outer: invalid value at line 3 column 3");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
 
         assert_eq!(
-            "outer: missing field `field_2` at line 4 column 3",
-            error.to_string()
+            Some(((2, 2), (2, 3))),
+            error_and_context.to_lsp_range(file_contents)
         );
+    }
+
+    #[test]
+    fn to_lsp_range_counts_unicode_line_separator() {
+        let file_contents = "a: 1\u{2028}b: x\n";
+        let error = serde_yaml::Error::custom("invalid value at line 2 column 4");
+        let error_and_context = ErrorAndContext::new(file_contents, &error);
+
         assert_eq!(
-            ErrorAndContext {
-                error_span: Some(SourceOffset::from_location(
-                    file_contents,
-                    loc_line,
-                    loc_col
-                )),
-                error_message: "outer: missing field `field_2`".to_string(),
-                context_span: None,
-            },
-            error_and_context,
-            "{error}"
+            Some(((1, 3), (1, 4))),
+            error_and_context.to_lsp_range(file_contents)
         );
     }
 
     #[test]
-    fn returns_source_offsets_for_null_variant() {
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Config {
-            outer: Outer,
-        }
+    fn error_range_and_context_range_none_without_location() {
+        let error = serde_yaml::Error::custom("invalid value");
+        let error_and_context = ErrorAndContext::new("a: 1\n", &error);
 
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        struct Outer {
-            inner: Inner,
-        }
+        assert_eq!(
+            (None, None),
+            (
+                error_and_context.error_range(),
+                error_and_context.context_range()
+            )
+        );
+    }
 
-        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
-        enum Inner {
-            One { value: u32 },
-            Two { value: u32 },
-        }
+    #[cfg(feature = "codespan")]
+    #[test]
+    fn to_codespan_diagnostic_labels_error_and_context() {
+        use codespan_reporting::{
+            diagnostic::{Label, LabelStyle},
+            files::SimpleFiles,
+            term::{self, Config},
+        };
 
-        let file_contents = r#"---
-outer:
-  inner: ~ # null variant
-#        ^
-#        '-- source offset is here.
-"#;
-        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
-        let error_and_context = ErrorAndContext::new(file_contents, &error);
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::builder()
+            .span_len_strategy(SpanLen::Token)
+            .build(file_contents, &error);
 
-        let loc_line = 3;
-        let loc_col = 10; // index 9 is column 10
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("config.yaml", file_contents);
+        let diagnostic = error_and_context.to_codespan_diagnostic(file_id);
 
+        assert_eq!("invalid value", diagnostic.message);
         assert_eq!(
-            "outer.inner: unknown variant `~`, expected `One` or `Two` at line 3 column 10",
-            error.to_string()
+            vec![
+                Label::new(LabelStyle::Primary, file_id, 13..20),
+                Label::new(LabelStyle::Secondary, file_id, 4..9)
+                    .with_message("while parsing this value"),
+            ],
+            diagnostic.labels
         );
+
+        let rendered = term::emit_into_string(&Config::default(), &files, &diagnostic).unwrap();
         assert_eq!(
-            ErrorAndContext {
-                error_span: Some(SourceOffset::from_location(
-                    file_contents,
-                    loc_line,
-                    loc_col
-                )),
-                error_message: "outer.inner: unknown variant `~`, expected `One` or `Two`"
-                    .to_string(),
-                context_span: None,
-            },
-            error_and_context,
-            "{error}"
+            r#"error: invalid value
+  ┌─ config.yaml:3:3
+  │
+2 │ outer:
+  │ ----- while parsing this value
+3 │   field_1: 123
+  │   ^^^^^^^
+
+"#,
+            rendered
         );
     }
 }
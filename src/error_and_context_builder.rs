@@ -0,0 +1,166 @@
+use crate::{ColumnBase, ErrorAndContext, LocationPattern, MarkOrder, SpanLen};
+
+/// Builder for an [`ErrorAndContext`] with non-default parsing options.
+///
+/// Use [`ErrorAndContext::new`] if the defaults are sufficient.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::ErrorAndContext;
+///
+/// let file_contents = "value: abc\n";
+/// let error =
+///     serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(file_contents).unwrap_err();
+///
+/// let error_and_context = ErrorAndContext::builder()
+///     .tab_width(4)
+///     .build(file_contents, &error);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorAndContextBuilder {
+    /// Width of a tab stop, if columns should be interpreted as display
+    /// columns.
+    pub(crate) tab_width: Option<usize>,
+    /// Text around each location in the error string.
+    pub(crate) location_pattern: LocationPattern,
+    /// Name of the source, e.g. the file path.
+    pub(crate) source_name: Option<String>,
+    /// Label for the context span.
+    pub(crate) context_message: Option<String>,
+    /// How the length of the error span is inferred.
+    pub(crate) span_len_strategy: SpanLen,
+    /// Whether to remove ANSI escape sequences from the error string.
+    pub(crate) strip_ansi: bool,
+    /// Which of the marks in the error string is the error.
+    pub(crate) mark_order: MarkOrder,
+    /// Whether to move the error span to the start of the token it is within.
+    pub(crate) snap_to_token_start: bool,
+    /// Whether columns in the error are counted from `0` or `1`.
+    pub(crate) column_base: ColumnBase,
+    /// Maximum number of characters in the error message.
+    pub(crate) max_message_len: Option<usize>,
+}
+
+impl ErrorAndContextBuilder {
+    /// Returns a new `ErrorAndContextBuilder` with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the text that precedes each location in the error string, defaults
+    /// to `" at "`.
+    ///
+    /// This is used both to truncate the locations from the error message, and
+    /// to find the locations, which is useful when `serde_yaml` is built with
+    /// translated messages.
+    ///
+    /// This replaces the [`location_pattern`] with the English pattern using
+    /// `delimiter` in place of `" at "`.
+    ///
+    /// [`location_pattern`]: Self::location_pattern
+    pub fn delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.location_pattern = LocationPattern::with_delimiter(&delimiter.into());
+        self
+    }
+
+    /// Sets the text around each location in the error string, defaults to
+    /// [`LocationPattern::default`].
+    ///
+    /// Use this when `serde_yaml` error strings have been translated.
+    pub fn location_pattern(mut self, location_pattern: LocationPattern) -> Self {
+        self.location_pattern = location_pattern;
+        self
+    }
+
+    /// Sets the name of the source, e.g. the file path.
+    pub fn source_name(mut self, source_name: impl Into<String>) -> Self {
+        self.source_name = Some(source_name.into());
+        self
+    }
+
+    /// Interprets columns as display columns, with tabs expanded to the next
+    /// multiple of `tab_width`.
+    ///
+    /// By default a tab is counted as one column, which is how `serde_yaml`
+    /// counts columns. Use this when the columns were computed against content
+    /// that is displayed with expanded tabs, so that the caret lines up with
+    /// what is displayed.
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = Some(tab_width);
+        self
+    }
+
+    /// Sets the label for the context span, defaults to `"while parsing this
+    /// value"`.
+    ///
+    /// This is only used when the context span is present.
+    pub fn context_message(mut self, context_message: impl Into<String>) -> Self {
+        self.context_message = Some(context_message.into());
+        self
+    }
+
+    /// Sets how the length of the error span is inferred, defaults to
+    /// [`SpanLen::Char`].
+    pub fn span_len_strategy(mut self, span_len_strategy: SpanLen) -> Self {
+        self.span_len_strategy = span_len_strategy;
+        self
+    }
+
+    /// Removes ANSI escape sequences, such as color codes, from the error
+    /// string before it is parsed, defaults to `false`.
+    ///
+    /// Use this when a wrapper has added color to the `serde_yaml` error's
+    /// `Display` string.
+    pub fn strip_ansi(mut self, strip_ansi: bool) -> Self {
+        self.strip_ansi = strip_ansi;
+        self
+    }
+
+    /// Sets which of the marks in the error string is the error, and which are
+    /// the context, defaults to [`MarkOrder::Auto`].
+    pub fn mark_order(mut self, mark_order: MarkOrder) -> Self {
+        self.mark_order = mark_order;
+        self
+    }
+
+    /// Moves the [`error_span`] back to the start of the token it is within,
+    /// defaults to `false`.
+    ///
+    /// Use this when the reported column may be in the middle of a scalar, so
+    /// that the whole scalar is underlined.
+    ///
+    /// [`error_span`]: ErrorAndContext::error_span
+    pub fn snap_to_token_start(mut self, snap_to_token_start: bool) -> Self {
+        self.snap_to_token_start = snap_to_token_start;
+        self
+    }
+
+    /// Sets whether columns in the error are counted from `0` or `1`, defaults
+    /// to [`ColumnBase::OneBased`].
+    pub fn column_base(mut self, column_base: ColumnBase) -> Self {
+        self.column_base = column_base;
+        self
+    }
+
+    /// Truncates the [`error_message`] to `max_message_len` characters, ending
+    /// with `…`, defaults to unlimited.
+    ///
+    /// A `max_message_len` of `0` gives an empty message.
+    ///
+    /// Use this when messages can be very long, such as when many variants are
+    /// expected.
+    ///
+    /// [`error_message`]: ErrorAndContext::error_message
+    pub fn max_message_len(mut self, max_message_len: usize) -> Self {
+        self.max_message_len = Some(max_message_len);
+        self
+    }
+
+    /// Returns the [`ErrorAndContext`] for the given error.
+    ///
+    /// See [`ErrorAndContext::new`].
+    pub fn build(&self, file_contents: &str, error: &serde_yaml::Error) -> ErrorAndContext {
+        ErrorAndContext::build(self, file_contents, error)
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use miette::SourceOffset;
+use serde::{Deserialize, Serialize};
+
+use crate::{ErrorAndContext, ErrorMessage};
+
+/// Serializable representation of an [`ErrorAndContext`].
+///
+/// [`SourceOffset`]s are stored as plain byte offsets.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct ErrorAndContextRepr {
+    /// Byte offset of the error.
+    error_span: Option<usize>,
+    /// Length of the underline at the error span.
+    error_span_len: Option<usize>,
+    /// The error message with the source offsets truncated.
+    error_message: String,
+    /// The path to the erroneous value.
+    path: Option<String>,
+    /// Byte offset of the surrounding context.
+    context_span: Option<usize>,
+    /// Length of the underline at the context span.
+    context_span_len: Option<usize>,
+    /// Label for the context span.
+    context_message: Option<String>,
+    /// Name of the source, e.g. the file path.
+    source_name: Option<String>,
+    /// The 1-based line and column of the error.
+    error_line_column: Option<(usize, usize)>,
+    /// The 1-based line and column of the surrounding context.
+    context_line_column: Option<(usize, usize)>,
+    /// The YAML that was deserialized, if it was stored.
+    source: Option<String>,
+    /// The `(index, line, column)` of the error as reported by `serde_yaml`.
+    reported_location: Option<(usize, usize, usize)>,
+    /// Byte offset of the mapping key whose value has the error.
+    key_span: Option<usize>,
+    /// Byte offsets of each surrounding context, from innermost to outermost.
+    context_spans: Vec<usize>,
+}
+
+impl From<ErrorAndContext> for ErrorAndContextRepr {
+    fn from(error_and_context: ErrorAndContext) -> Self {
+        let ErrorAndContext {
+            error_span,
+            error_span_len,
+            error_message,
+            path,
+            context_span,
+            context_span_len,
+            context_message,
+            source_name,
+            error_line_column,
+            context_line_column,
+            source,
+            reported_location,
+            key_span,
+            context_spans,
+        } = error_and_context;
+
+        Self {
+            error_span: error_span.map(|error_span| error_span.offset()),
+            error_span_len,
+            error_message: error_message.into_string(),
+            path,
+            context_span: context_span.map(|context_span| context_span.offset()),
+            context_span_len,
+            context_message,
+            source_name,
+            error_line_column,
+            context_line_column,
+            source: source.map(|source| source.to_string()),
+            reported_location,
+            key_span: key_span.map(|key_span| key_span.offset()),
+            context_spans: context_spans
+                .into_iter()
+                .map(|context_span| context_span.offset())
+                .collect(),
+        }
+    }
+}
+
+impl From<ErrorAndContextRepr> for ErrorAndContext {
+    fn from(error_and_context_repr: ErrorAndContextRepr) -> Self {
+        let ErrorAndContextRepr {
+            error_span,
+            error_span_len,
+            error_message,
+            path,
+            context_span,
+            context_span_len,
+            context_message,
+            source_name,
+            error_line_column,
+            context_line_column,
+            source,
+            reported_location,
+            key_span,
+            context_spans,
+        } = error_and_context_repr;
+
+        Self {
+            error_span: error_span.map(SourceOffset::from),
+            error_span_len,
+            error_message: ErrorMessage::from(error_message),
+            path,
+            context_span: context_span.map(SourceOffset::from),
+            context_span_len,
+            context_message,
+            source_name,
+            error_line_column,
+            context_line_column,
+            source: source.map(Arc::from),
+            reported_location,
+            key_span: key_span.map(SourceOffset::from),
+            context_spans: context_spans.into_iter().map(SourceOffset::from).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::Error as _;
+
+    use crate::ErrorAndContext;
+
+    #[test]
+    fn json_round_trip() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error =
+            serde_yaml::Error::custom("invalid value at line 3 column 3 at line 2 column 1");
+        let error_and_context = ErrorAndContext::new_owned(file_contents, &error);
+
+        let json = serde_json::to_string(&error_and_context).unwrap();
+        let error_and_context_deserialized =
+            serde_json::from_str::<ErrorAndContext>(&json).unwrap();
+
+        assert_eq!(
+            r#"{"error_span":13,"error_span_len":1,"error_message":"invalid value","path":null,"context_span":4,"context_span_len":1,"context_message":"while parsing this value","source_name":null,"error_line_column":[3,3],"context_line_column":[2,1],"source":"---\nouter:\n  field_1: 123\n","reported_location":null,"key_span":null,"context_spans":[4]}"#,
+            json
+        );
+        assert_eq!(error_and_context, error_and_context_deserialized);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{fmt, ops::Deref};
+
+/// The error message of an [`ErrorAndContext`], with the source offsets
+/// truncated.
+///
+/// This dereferences to `str`, so it can be used wherever a `&str` is
+/// expected.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::ErrorMessage;
+///
+/// let error_message = ErrorMessage::from("invalid value");
+///
+/// assert_eq!("invalid value", error_message.as_ref());
+/// assert!(error_message.starts_with("invalid"));
+/// ```
+///
+/// [`ErrorAndContext`]: crate::ErrorAndContext
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ErrorMessage(String);
+
+impl ErrorMessage {
+    /// Returns a new `ErrorMessage`.
+    pub fn new(error_message: impl Into<String>) -> Self {
+        Self(error_message.into())
+    }
+
+    /// Returns the message as a `&str`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the underlying `String`.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl AsRef<str> for ErrorMessage {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for ErrorMessage {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<String> for ErrorMessage {
+    fn from(error_message: String) -> Self {
+        Self(error_message)
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    fn from(error_message: &str) -> Self {
+        Self(error_message.to_string())
+    }
+}
+
+impl From<ErrorMessage> for String {
+    fn from(error_message: ErrorMessage) -> Self {
+        error_message.0
+    }
+}
+
+impl PartialEq<str> for ErrorMessage {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ErrorMessage {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for ErrorMessage {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<ErrorMessage> for str {
+    fn eq(&self, other: &ErrorMessage) -> bool {
+        self == other.0
+    }
+}
+
+impl PartialEq<ErrorMessage> for &str {
+    fn eq(&self, other: &ErrorMessage) -> bool {
+        *self == other.0
+    }
+}
+
+impl PartialEq<ErrorMessage> for String {
+    fn eq(&self, other: &ErrorMessage) -> bool {
+        *self == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorMessage;
+
+    fn message_len(message: impl AsRef<str>) -> usize {
+        message.as_ref().len()
+    }
+
+    #[test]
+    fn as_ref_str() {
+        let error_message = ErrorMessage::from("invalid value");
+
+        assert_eq!("invalid value", error_message.as_ref());
+        assert_eq!(13, message_len(&error_message));
+    }
+
+    #[test]
+    fn derefs_and_compares_with_str() {
+        let error_message = ErrorMessage::new(String::from("outer: invalid value"));
+
+        assert_eq!(
+            Some(("outer", "invalid value")),
+            error_message.split_once(": ")
+        );
+        assert_eq!("outer: invalid value", error_message);
+        assert_eq!(error_message, "outer: invalid value");
+        assert_eq!("outer: invalid value", error_message.to_string());
+        assert_eq!(
+            String::from("outer: invalid value"),
+            error_message.into_string()
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use miette::SourceOffset;
+
+use crate::scan;
+
+/// Returns the location of the value at `path` within `file_contents`.
+///
+/// Use this for errors found after deserializing, such as from validating a
+/// [`serde_yaml::Value`], so that they can be shown against the YAML with the
+/// same spans as deserialization errors.
+///
+/// Each key in `path` is in the block mapping that is the value of the key
+/// before it. Flow mappings and sequence indices are not navigated.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::{find_path_span, SourceOffset};
+///
+/// let file_contents = "---\nouter:\n  field_1: 123\n";
+///
+/// assert_eq!(
+///     Some(SourceOffset::from(22)),
+///     find_path_span(file_contents, &["outer", "field_1"])
+/// );
+/// ```
+pub fn find_path_span(file_contents: &str, path: &[&str]) -> Option<SourceOffset> {
+    let key_occurrence = scan::key_path(file_contents, path)?;
+    scan::value_offset(file_contents, key_occurrence.offset).map(SourceOffset::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceOffset;
+
+    use super::find_path_span;
+
+    #[test]
+    fn outer_field_1() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+
+        assert_eq!(
+            Some(SourceOffset::from(22)),
+            find_path_span(file_contents, &["outer", "field_1"])
+        );
+        assert_eq!(
+            Some(SourceOffset::from(13)),
+            find_path_span(file_contents, &["outer"])
+        );
+    }
+
+    #[test]
+    fn none_when_path_not_found() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+
+        assert_eq!(None, find_path_span(file_contents, &["outer", "field_2"]));
+        assert_eq!(None, find_path_span(file_contents, &["field_1"]));
+        assert_eq!(None, find_path_span(file_contents, &[]));
+    }
+}
@@ -0,0 +1,82 @@
+use serde::de::DeserializeOwned;
+
+use crate::YamlError;
+
+/// Deserializes `file_contents`, returning a [`YamlError`] with the error
+/// location and message on failure.
+///
+/// This is [`serde_yaml::from_str`] followed by [`YamlError::from_serde`].
+/// The file contents are only copied into the error on failure.
+///
+/// # Examples
+///
+/// ```rust
+/// let value = yaml_error_context_hack::from_str::<u32>("123").unwrap();
+/// assert_eq!(123, value);
+///
+/// let error = yaml_error_context_hack::from_str::<u32>("abc").unwrap_err();
+/// assert_eq!(
+///     "invalid type: string \"abc\", expected u32",
+///     error.error_and_context().error_message
+/// );
+/// ```
+pub fn from_str<T>(file_contents: &str) -> Result<T, YamlError>
+where
+    T: DeserializeOwned,
+{
+    serde_yaml::from_str(file_contents).map_err(|error| YamlError::from_serde(file_contents, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceOffset;
+    use serde::{Deserialize, Serialize};
+
+    use super::from_str;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    struct Config {
+        outer: Outer,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    struct Outer {
+        field_1: u32,
+        field_2: u32,
+    }
+
+    #[test]
+    fn ok_returns_value() {
+        let config = from_str::<Config>("outer:\n  field_1: 1\n  field_2: 2\n").unwrap();
+
+        assert_eq!(
+            Config {
+                outer: Outer {
+                    field_1: 1,
+                    field_2: 2
+                }
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn err_returns_error_and_context() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error = from_str::<Config>(file_contents).unwrap_err();
+        let error_and_context = error.error_and_context();
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3)),
+                "outer: missing field `field_2`",
+                file_contents,
+            ),
+            (
+                error_and_context.error_span,
+                error_and_context.error_message.as_str(),
+                error.source_str(),
+            )
+        );
+    }
+}
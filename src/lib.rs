@@ -1,8 +1,16 @@
 //! Returns the `serde_yaml` error location and message to pass to `miette`.
 //!
+//! # Features
+//!
+//! * `std` (default): [`ErrorAndContext`] and the other `serde_yaml` and
+//!   `miette` integrations. Without it, only [`parse_marks`] and
+//!   [`LocationPattern`] are available, and only `alloc` is required.
+//!
 //! # Examples
 //!
 //! ```rust
+//! # #[cfg(feature = "std")]
+//! # {
 //! use serde::{Deserialize, Serialize};
 //! use yaml_error_context_hack::{ErrorAndContext, SourceOffset};
 //!
@@ -34,23 +42,86 @@
 //!     error.to_string()
 //! );
 //! assert_eq!(
-//!     ErrorAndContext {
-//!         error_span: Some(SourceOffset::from_location(
-//!             file_contents,
-//!             loc_line,
-//!             loc_col
-//!         )),
-//!         error_message: "outer: missing field `field_2`".to_string(),
-//!         context_span: None,
-//!     },
-//!     error_and_context,
-//!     "{error}"
+//!     Some(SourceOffset::from_location(
+//!         file_contents,
+//!         loc_line,
+//!         loc_col
+//!     )),
+//!     error_and_context.error_span
+//! );
+//! assert_eq!(Some(1), error_and_context.error_span_len);
+//! assert_eq!(
+//!     "outer: missing field `field_2`",
+//!     error_and_context.error_message
 //! );
+//! assert_eq!(Some("outer"), error_and_context.path.as_deref());
+//! assert_eq!(None, error_and_context.context_span);
+//! # }
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 // Re-exports
+#[cfg(feature = "std")]
 pub use miette::{self, SourceOffset};
 
-pub use crate::error_and_context::ErrorAndContext;
+pub use crate::{location_pattern::LocationPattern, parse_marks::parse_marks};
+
+#[cfg(feature = "std")]
+pub use crate::{
+    column_base::ColumnBase, encoding::Encoding, error_and_context::ErrorAndContext,
+    error_and_context_builder::ErrorAndContextBuilder, error_message::ErrorMessage,
+    find_path_span::find_path_span, from_str::from_str, mark_order::MarkOrder,
+    multi_error::MultiError, path_segment::PathSegment, span_len::SpanLen,
+    spanned_error::SpannedError, yaml_error::YamlError, yaml_error_kind::YamlErrorKind,
+    yaml_result_ext::YamlResultExt,
+};
+
+// Only the parsing is used without `std`.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+mod line_index;
+mod location_pattern;
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+mod mark;
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+mod message;
+mod parse_marks;
 
+#[cfg(feature = "std")]
+mod column_base;
+#[cfg(feature = "std")]
+mod encoding;
+#[cfg(feature = "std")]
 mod error_and_context;
+#[cfg(feature = "std")]
+mod error_and_context_builder;
+#[cfg(feature = "serde")]
+mod error_and_context_repr;
+#[cfg(feature = "std")]
+mod error_message;
+#[cfg(feature = "std")]
+mod find_path_span;
+#[cfg(feature = "std")]
+mod from_str;
+#[cfg(feature = "std")]
+mod mark_order;
+#[cfg(feature = "std")]
+mod multi_error;
+#[cfg(feature = "std")]
+mod path_segment;
+#[cfg(feature = "std")]
+mod scan;
+#[cfg(feature = "std")]
+mod span_len;
+#[cfg(feature = "std")]
+mod spanned_error;
+#[cfg(feature = "std")]
+mod token;
+#[cfg(feature = "std")]
+mod yaml_error;
+#[cfg(feature = "std")]
+mod yaml_error_kind;
+#[cfg(feature = "std")]
+mod yaml_result_ext;
@@ -0,0 +1,383 @@
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+/// Byte offsets of the start of each line, so that multiple marks can be
+/// resolved without rescanning the source from the beginning.
+///
+/// This differs from [`miette::SourceOffset::from_location`] by matching how
+/// libyaml counts lines:
+///
+/// * `"\r\n"`, `"\r"`, and `"\n"` are each a single line break.
+/// * The next line (`U+0085`), line separator (`U+2028`), and paragraph
+///   separator (`U+2029`) characters are also line breaks.
+/// * A column past the end of a line stays on that line, instead of wrapping
+///   onto following lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LineIndex<'s> {
+    /// The source that the line starts are computed for.
+    file_contents: &'s str,
+    /// Byte offset of the start of each line, the first is always `0`.
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    /// Returns a new `LineIndex` for `file_contents`.
+    pub(crate) fn new(file_contents: &'s str) -> Self {
+        let mut line_starts = vec![0];
+        let mut char_indices = file_contents.char_indices().peekable();
+        while let Some((offset, c)) = char_indices.next() {
+            match c {
+                '\n' | '\u{85}' | '\u{2028}' | '\u{2029}' => {
+                    line_starts.push(offset + c.len_utf8());
+                }
+                '\r' => match char_indices.next_if(|(_, c_next)| *c_next == '\n') {
+                    Some((offset_lf, _)) => line_starts.push(offset_lf + 1),
+                    None => line_starts.push(offset + 1),
+                },
+                _ => {}
+            }
+        }
+
+        Self {
+            file_contents,
+            line_starts,
+        }
+    }
+
+    /// Returns the byte offset of a 1-based `line` and `column`.
+    ///
+    /// Columns are counted in characters, not bytes. If `tab_width` is
+    /// provided, columns are display columns, where a tab advances to the next
+    /// multiple of `tab_width`.
+    pub(crate) fn offset(&self, line: usize, column: usize, tab_width: Option<usize>) -> usize {
+        let Some(line_start) = self.line_starts.get(line.max(1) - 1).copied() else {
+            return self.file_contents.len();
+        };
+        let column_target = column.max(1);
+
+        let mut column = 1;
+        for (offset, c) in self.file_contents[line_start..].char_indices() {
+            let column_next = match (c, tab_width) {
+                ('\t', Some(tab_width)) => {
+                    let tab_width = tab_width.max(1);
+                    ((column - 1) / tab_width + 1) * tab_width + 1
+                }
+                _ => column + 1,
+            };
+            if is_line_break(c) || column_target < column_next {
+                return line_start + offset;
+            }
+
+            column = column_next;
+        }
+
+        self.file_contents.len()
+    }
+
+    /// Returns the byte `index` clamped to the length of the source and moved
+    /// back to the nearest character boundary.
+    pub(crate) fn offset_clamped(&self, index: usize) -> usize {
+        let mut offset = index.min(self.file_contents.len());
+        while !self.file_contents.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        offset
+    }
+
+    /// Returns the 1-based line and column of a byte `offset`.
+    ///
+    /// Columns are counted in characters, not bytes.
+    pub(crate) fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line = self
+            .line_starts
+            .partition_point(|line_start| *line_start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self
+            .file_contents
+            .get(line_start..offset)
+            .map_or(0, |line_prefix| line_prefix.chars().count());
+
+        (line, column + 1)
+    }
+}
+
+/// Returns whether libyaml treats `c` as a line break.
+pub(crate) fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+/// Returns the byte offset and text of each line, without line breaks.
+///
+/// `"\r\n"` is one line break, and every other [`is_line_break`] character is
+/// one line break.
+pub(crate) fn lines(file_contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut line_start = Some(0);
+    core::iter::from_fn(move || {
+        let offset = line_start?;
+        let rest = &file_contents[offset..];
+        match rest.char_indices().find(|(_, c)| is_line_break(*c)) {
+            Some((line_len, c)) => {
+                let line_break_len = if rest[line_len..].starts_with("\r\n") {
+                    2
+                } else {
+                    c.len_utf8()
+                };
+                line_start = Some(offset + line_len + line_break_len);
+                Some((offset, &rest[..line_len]))
+            }
+            None => {
+                line_start = None;
+                Some((offset, rest))
+            }
+        }
+    })
+}
+
+/// Returns the text of the line that `offset` falls on, without the line
+/// break.
+///
+/// An `offset` past the end of `file_contents` falls on the last line.
+pub(crate) fn line_text(file_contents: &str, offset: usize) -> &str {
+    &file_contents[line_range(file_contents, offset)]
+}
+
+/// Returns the byte range of the line that `offset` falls on, without the line
+/// break.
+///
+/// An `offset` past the end of `file_contents` falls on the last line.
+pub(crate) fn line_range(file_contents: &str, offset: usize) -> Range<usize> {
+    let mut offset = offset.min(file_contents.len());
+    while !file_contents.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = file_contents[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| is_line_break(*c))
+        .map_or(0, |(line_break_index, c)| line_break_index + c.len_utf8());
+    let line_end = file_contents[offset..]
+        .find(is_line_break)
+        .map_or(file_contents.len(), |line_break_index| {
+            offset + line_break_index
+        });
+
+    line_start..line_end
+}
+
+/// Returns the 0-based line and UTF-16 character of `offset`, as used by the
+/// Language Server Protocol.
+///
+/// Line breaks are counted the same way as [`lines`]. An `offset` past the end
+/// of `file_contents` is at the end.
+pub(crate) fn lsp_position(file_contents: &str, offset: usize) -> (u32, u32) {
+    let mut offset = offset.min(file_contents.len());
+    while !file_contents.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let (line_start, line) = lines(&file_contents[..offset])
+        .enumerate()
+        .last()
+        .map_or((0, 0), |(line, (line_start, _))| (line_start, line));
+    let character = file_contents[line_start..offset].encode_utf16().count();
+
+    (
+        u32::try_from(line).unwrap_or(u32::MAX),
+        u32::try_from(character).unwrap_or(u32::MAX),
+    )
+}
+
+/// Returns the byte offset of a 1-based `line` and a `column` that counts
+/// bytes, as reported by `serde_json`.
+///
+/// Only `"\n"` is a line break, and a `column` of `0` is the start of the line.
+/// The offset is moved back to the nearest character boundary.
+#[cfg(feature = "json")]
+pub(crate) fn offset_byte_column(file_contents: &str, line: usize, column: usize) -> usize {
+    let line_start = file_contents
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    let mut offset = (line_start + column.saturating_sub(1)).min(file_contents.len());
+    while !file_contents.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceOffset;
+
+    use super::{line_text, lines, lsp_position, LineIndex};
+
+    #[test]
+    fn lines_split_on_libyaml_line_breaks() {
+        let file_contents = "a\r\nb\rc\u{2028}d\u{85}e\n";
+
+        assert_eq!(
+            vec![(0, "a"), (3, "b"), (5, "c"), (9, "d"), (12, "e"), (14, "")],
+            lines(file_contents).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn lf_matches_miette_from_location() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+
+        assert_eq!(
+            SourceOffset::from_location(file_contents, 3, 3).offset(),
+            LineIndex::new(file_contents).offset(3, 3, None)
+        );
+    }
+
+    #[test]
+    fn unicode_line_separators_are_line_breaks() {
+        let file_contents = "a: 1\u{2028}b: 2\u{85}c: 3\u{2029}d: 4\n";
+        let line_index = LineIndex::new(file_contents);
+
+        assert_eq!(
+            vec![
+                file_contents.find('b').unwrap(),
+                file_contents.find('c').unwrap(),
+                file_contents.find('d').unwrap(),
+            ],
+            vec![
+                line_index.offset(2, 1, None),
+                line_index.offset(3, 1, None),
+                line_index.offset(4, 1, None),
+            ]
+        );
+        assert_eq!(
+            (2, 4),
+            line_index.line_column(file_contents.find("2").unwrap())
+        );
+    }
+
+    #[test]
+    fn column_past_line_end_stops_at_unicode_line_separator() {
+        let file_contents = "a: 1\u{2028}b: 2\n";
+
+        assert_eq!(4, LineIndex::new(file_contents).offset(1, 10, None));
+    }
+
+    #[test]
+    fn crlf_is_one_line_break() {
+        let file_contents = "---\r\nouter:\r\n  field_1: 123\r\n";
+
+        assert_eq!(15, LineIndex::new(file_contents).offset(3, 3, None));
+    }
+
+    #[test]
+    fn cr_is_one_line_break() {
+        let file_contents = "---\router:\r  field_1: 123\r";
+
+        assert_eq!(13, LineIndex::new(file_contents).offset(3, 3, None));
+    }
+
+    #[test]
+    fn column_counts_multibyte_characters_once() {
+        let file_contents = "é: 1\n😀: abc\n";
+
+        assert_eq!(
+            file_contents.find("abc").unwrap(),
+            LineIndex::new(file_contents).offset(2, 4, None)
+        );
+    }
+
+    #[test]
+    fn column_past_end_of_line_stays_on_line() {
+        let file_contents = "a: 1\nb: 2\n";
+
+        assert_eq!(4, LineIndex::new(file_contents).offset(1, 10, None));
+    }
+
+    #[test]
+    fn line_past_end_of_file_is_end_of_file() {
+        let file_contents = "a: 1\nb: 2\n";
+
+        assert_eq!(10, LineIndex::new(file_contents).offset(5, 1, None));
+    }
+
+    #[test]
+    fn tab_is_one_column_by_default() {
+        let file_contents = "\tvalue: abc\n";
+
+        assert_eq!(1, LineIndex::new(file_contents).offset(1, 2, None));
+    }
+
+    #[test]
+    fn tab_width_expands_tab_to_next_tab_stop() {
+        let file_contents = "\tvalue: abc\n";
+
+        assert_eq!(0, LineIndex::new(file_contents).offset(1, 3, Some(4)));
+        assert_eq!(1, LineIndex::new(file_contents).offset(1, 5, Some(4)));
+        assert_eq!(8, LineIndex::new(file_contents).offset(1, 12, Some(4)));
+    }
+
+    #[test]
+    fn tab_width_expands_mid_line_tab_to_next_tab_stop() {
+        let file_contents = "ab\tcd\n";
+
+        assert_eq!(3, LineIndex::new(file_contents).offset(1, 5, Some(4)));
+    }
+
+    #[test]
+    fn resolves_multiple_marks_against_one_index() {
+        let file_contents = "a: 1\r\nb: 2\rc: 3\nd: 4";
+        let line_index = LineIndex::new(file_contents);
+
+        assert_eq!(0, line_index.offset(1, 1, None));
+        assert_eq!(9, line_index.offset(2, 4, None));
+        assert_eq!(14, line_index.offset(3, 4, None));
+        assert_eq!(17, line_index.offset(4, 2, None));
+    }
+
+    #[test]
+    fn line_column_is_inverse_of_offset() {
+        let file_contents = "a: 1\r\nb: é2\rc: 3\nd: 4";
+        let line_index = LineIndex::new(file_contents);
+
+        [(1, 1), (2, 4), (2, 5), (3, 4), (4, 2)]
+            .into_iter()
+            .for_each(|(line, column)| {
+                let offset = line_index.offset(line, column, None);
+                assert_eq!((line, column), line_index.line_column(offset));
+            });
+    }
+
+    #[test]
+    fn offset_clamped_to_len_and_char_boundary() {
+        let line_index = LineIndex::new("a: é");
+
+        assert_eq!(5, line_index.offset_clamped(100));
+        assert_eq!(3, line_index.offset_clamped(4));
+        assert_eq!(1, line_index.offset_clamped(1));
+    }
+
+    #[test]
+    fn line_text_excludes_line_breaks() {
+        let file_contents = "a: 1\r\nb: 2\rc: 3\nd: 4";
+
+        assert_eq!("a: 1", line_text(file_contents, 0));
+        assert_eq!("a: 1", line_text(file_contents, 4));
+        assert_eq!("b: 2", line_text(file_contents, 6));
+        assert_eq!("c: 3", line_text(file_contents, 13));
+        assert_eq!("d: 4", line_text(file_contents, 100));
+    }
+
+    #[test]
+    fn lsp_position_counts_utf16_and_line_breaks() {
+        let file_contents = "a: 1\r\nb: 2\rc: \"😀x\"\n";
+
+        assert_eq!((0, 0), lsp_position(file_contents, 0));
+        assert_eq!((1, 1), lsp_position(file_contents, 7));
+        assert_eq!(
+            (2, 6),
+            lsp_position(file_contents, file_contents.find('x').unwrap())
+        );
+        assert_eq!((3, 0), lsp_position(file_contents, file_contents.len()));
+    }
+}
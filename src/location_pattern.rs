@@ -0,0 +1,128 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+/// The text around each location in a `serde_yaml` error string.
+///
+/// libyaml renders a location as `" at line 2 column 3"`, or `" at position
+/// 42"` when the line and column are both zero. Use a different pattern when
+/// the error strings have been translated, so that the locations can still be
+/// found and removed from the error message.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::LocationPattern;
+///
+/// let location_pattern = LocationPattern {
+///     at_line: String::from(" en la línea "),
+///     column: String::from(" columna "),
+///     at_position: String::from(" en la posición "),
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocationPattern {
+    /// Text before the line number, defaults to `" at line "`.
+    pub at_line: String,
+    /// Text between the line and column numbers, defaults to `" column "`.
+    pub column: String,
+    /// Text before the byte position, defaults to `" at position "`.
+    pub at_position: String,
+}
+
+impl LocationPattern {
+    /// Returns the English pattern with `delimiter` in place of `" at "`.
+    pub fn with_delimiter(delimiter: &str) -> Self {
+        Self {
+            at_line: format!("{delimiter}line "),
+            column: String::from(" column "),
+            at_position: format!("{delimiter}position "),
+        }
+    }
+
+    /// Returns `text` after the text before a column number that is not
+    /// preceded by a line number, e.g. `" at column "`.
+    ///
+    /// This text is the delimiter before the last word of [`at_line`],
+    /// followed by the [`column`] text, so that translated patterns are also
+    /// matched. Returns `None` when [`at_line`] has no delimiter, e.g. for
+    /// [`LocationPattern::with_delimiter("")`], as a bare `" column "` would
+    /// also match ordinary text in the message.
+    ///
+    /// [`at_line`]: Self::at_line
+    /// [`column`]: Self::column
+    /// [`LocationPattern::with_delimiter("")`]: Self::with_delimiter
+    pub(crate) fn strip_at_column<'s>(&self, text: &'s str) -> Option<&'s str> {
+        let (delimiter, _line) = self.at_line.trim_end().rsplit_once(' ')?;
+        if delimiter.trim().is_empty() {
+            return None;
+        }
+
+        let at_column = format!("{delimiter} {}", self.column.trim_start());
+        text.strip_prefix(at_column.as_str())
+    }
+
+    /// Returns this pattern with leading whitespace removed, to match a
+    /// location at the start of the error string.
+    pub(crate) fn trim_start(&self) -> Self {
+        Self {
+            at_line: self.at_line.trim_start().to_string(),
+            column: self.column.clone(),
+            at_position: self.at_position.trim_start().to_string(),
+        }
+    }
+}
+
+impl Default for LocationPattern {
+    fn default() -> Self {
+        Self::with_delimiter(" at ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocationPattern;
+
+    #[test]
+    fn strip_at_column_from_at_line() {
+        let location_pattern = LocationPattern::default();
+        assert_eq!(Some("7"), location_pattern.strip_at_column(" at column 7"));
+        assert_eq!(
+            Some("7"),
+            location_pattern.trim_start().strip_at_column("at column 7")
+        );
+        assert_eq!(None, location_pattern.strip_at_column(" at line 7"));
+
+        let location_pattern = LocationPattern {
+            at_line: String::from(" en la línea "),
+            column: String::from(" columna "),
+            at_position: String::from(" en la posición "),
+        };
+        assert_eq!(
+            Some("7"),
+            location_pattern.strip_at_column(" en la columna 7")
+        );
+    }
+
+    #[test]
+    fn strip_at_column_none_without_delimiter() {
+        let location_pattern = LocationPattern::with_delimiter("");
+        assert_eq!(None, location_pattern.strip_at_column(" column 7"));
+        assert_eq!(None, location_pattern.strip_at_column("column 7"));
+
+        let location_pattern = LocationPattern {
+            at_line: String::from(" line "),
+            column: String::from(" column "),
+            at_position: String::from(" position "),
+        };
+        assert_eq!(None, location_pattern.strip_at_column(" column 7"));
+
+        let location_pattern = LocationPattern {
+            at_line: String::from("@"),
+            column: String::from(":"),
+            at_position: String::from("#"),
+        };
+        assert_eq!(None, location_pattern.strip_at_column(":7"));
+    }
+}
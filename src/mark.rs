@@ -0,0 +1,484 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{line_index::LineIndex, LocationPattern};
+
+/// A location parsed from the `Display` string of a `serde_yaml` error.
+///
+/// libyaml renders a mark as either `"line 2 column 3"`, or `"position 42"`
+/// when the line and column are both zero.
+///
+/// See <https://github.com/dtolnay/serde-yaml/blob/0.9.34/src/libyaml/error.rs#L135-L149>.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mark {
+    /// 1-based line and column, e.g. `"line 2 column 3"`.
+    LineColumn { line: usize, column: usize },
+    /// Byte index into the source, e.g. `"position 42"`.
+    Position(usize),
+}
+
+impl Mark {
+    /// Parses a mark from the start of `text`, returning the mark and the text
+    /// after it.
+    ///
+    /// `text` must begin with the [`LocationPattern::at_line`] or
+    /// [`LocationPattern::at_position`], e.g. `" at line 2 column 3"`. A column
+    /// without a line, e.g. `" at column 7"`, is on line 1.
+    ///
+    /// Returns `None` if `text` does not begin with a mark.
+    pub(crate) fn parse_prefix<'s>(
+        text: &'s str,
+        location_pattern: &LocationPattern,
+    ) -> Option<(Self, &'s str)> {
+        let LocationPattern {
+            at_line,
+            column,
+            at_position,
+        } = location_pattern;
+
+        if let Some(line_column) = text.strip_prefix(at_line.as_str()) {
+            let (line, rest) = parse_usize_prefix(line_column)?;
+            let rest = rest.strip_prefix(column.as_str())?;
+            let (column, rest) = parse_usize_prefix(rest)?;
+
+            Some((Mark::LineColumn { line, column }, rest))
+        } else if let Some(position) = text.strip_prefix(at_position.as_str()) {
+            let (index, rest) = parse_usize_prefix(position)?;
+
+            Some((Mark::Position(index), rest))
+        } else if let Some(column) = location_pattern.strip_at_column(text) {
+            // A column without a line is for single line input.
+            let (column, rest) = parse_usize_prefix(column)?;
+
+            Some((Mark::LineColumn { line: 1, column }, rest))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the 1-based line and column of this mark, if it is not a
+    /// position.
+    pub(crate) fn line_column(self) -> Option<(usize, usize)> {
+        match self {
+            Mark::LineColumn { line, column } => Some((line, column)),
+            Mark::Position(_) => None,
+        }
+    }
+
+    /// Returns the byte offset of this mark within the indexed source.
+    ///
+    /// The offset is clamped to the length of the source.
+    ///
+    /// See [`LineIndex::offset`] for how `tab_width` is used.
+    pub(crate) fn offset(self, line_index: &LineIndex<'_>, tab_width: Option<usize>) -> usize {
+        match self {
+            Mark::LineColumn { line, column } => line_index.offset(line, column, tab_width),
+            Mark::Position(index) => line_index.offset_clamped(index),
+        }
+    }
+}
+
+/// Parses the leading ASCII digits of `text`, returning the number and the text
+/// after it.
+///
+/// Numbers that overflow `usize` saturate to `usize::MAX`, so that the mark is
+/// still recognized and its offset is clamped to the end of the source.
+fn parse_usize_prefix(text: &str) -> Option<(usize, &str)> {
+    let digits_len = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (digits, rest) = text.split_at(digits_len);
+    if digits.is_empty() {
+        return None;
+    }
+
+    let number = digits.bytes().fold(0usize, |number, digit| {
+        number
+            .saturating_mul(10)
+            .saturating_add(usize::from(digit - b'0'))
+    });
+
+    Some((number, rest))
+}
+
+/// A [`Mark`] found within an error string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MarkMatch {
+    /// The parsed mark.
+    pub(crate) mark: Mark,
+    /// Byte range of the mark within the error string, including the text
+    /// before the numbers.
+    pub(crate) range: Range<usize>,
+    /// Whether the mark is at the end of the error string, or immediately
+    /// followed by another mark.
+    pub(crate) is_trailing: bool,
+}
+
+impl MarkMatch {
+    /// Returns whether this mark is a location of the error rather than part of
+    /// the message, i.e. it is trailing, or ends a clause before a `,` such as
+    /// `"at line 4 column 10, while parsing ..."`.
+    pub(crate) fn is_location(&self, error_string: &str) -> bool {
+        self.is_trailing || error_string[self.range.end..].starts_with(',')
+    }
+}
+
+/// Returns the marks within `error_string` in order of appearance.
+///
+/// Text within backticks is skipped, as it is a field name or value that may
+/// itself contain `" at line 1 column 2"`.
+pub(crate) fn find_marks(error_string: &str, location_pattern: &LocationPattern) -> Vec<MarkMatch> {
+    let mut mark_matches = Vec::<MarkMatch>::new();
+    let mut search_start = 0;
+
+    // The error string may begin with a mark, without the leading whitespace, e.g.
+    // `"at line 2 column 3"`.
+    if let Some((mark, rest)) = Mark::parse_prefix(error_string, &location_pattern.trim_start()) {
+        search_start = error_string.len() - rest.len();
+        mark_matches.push(MarkMatch {
+            mark,
+            range: 0..search_start,
+            is_trailing: rest.is_empty(),
+        });
+    }
+    let mut in_backticks = false;
+    while let Some((index, mark, rest)) =
+        error_string[search_start..]
+            .char_indices()
+            .find_map(|(index, c)| {
+                if c == '`' {
+                    in_backticks = !in_backticks;
+                }
+                if in_backticks {
+                    return None;
+                }
+
+                let index = search_start + index;
+                Mark::parse_prefix(&error_string[index..], location_pattern)
+                    .map(|(mark, rest)| (index, mark, rest))
+            })
+    {
+        let end = error_string.len() - rest.len();
+        if let Some(previous) = mark_matches.last_mut() {
+            previous.is_trailing = previous.range.end == index;
+        }
+        mark_matches.push(MarkMatch {
+            mark,
+            range: index..end,
+            is_trailing: rest.is_empty(),
+        });
+        search_start = end;
+    }
+
+    mark_matches
+}
+
+/// Returns the error and context marks from the trailing marks of the error
+/// string, and marks that are followed by a `,`.
+///
+/// The first mark is the error, and each following mark is a context, from the
+/// innermost to the outermost. Consecutive repeated marks are only kept once.
+/// If there is only one mark, it is the error.
+pub(crate) fn error_and_context_marks(
+    error_string: &str,
+    location_pattern: &LocationPattern,
+) -> (Option<Mark>, Vec<Mark>) {
+    let mut marks = find_marks(error_string, location_pattern)
+        .into_iter()
+        .filter(|mark_match| mark_match.is_location(error_string))
+        .map(|mark_match| mark_match.mark)
+        .collect::<Vec<_>>();
+    marks.dedup();
+
+    let mut marks = marks.into_iter();
+    let error_mark = marks.next();
+    (error_mark, marks.collect())
+}
+
+/// Returns the problem and context marks of a libyaml error that has a
+/// `"while parsing ..."` or `"while scanning ..."` context.
+///
+/// libyaml renders these as:
+///
+/// ```text
+/// did not find expected ',' or ']' at line 2 column 6, while parsing a flow sequence at line 1 column 6
+/// ```
+///
+/// The context mark is omitted when it is at the start of the file, or at
+/// the problem mark. Returns `None` if the error string has no such context.
+pub(crate) fn problem_and_context_marks(
+    error_string: &str,
+    location_pattern: &LocationPattern,
+) -> Option<(Option<Mark>, Option<Mark>)> {
+    let context_start = [", while parsing ", ", while scanning "]
+        .into_iter()
+        .find_map(|while_context| error_string.find(while_context))?;
+    let (problem, context) = error_string.split_at(context_start);
+    let last_trailing_mark = |text: &str| {
+        find_marks(text, location_pattern)
+            .into_iter()
+            .rev()
+            .find(|mark_match| mark_match.is_trailing)
+            .map(|mark_match| mark_match.mark)
+    };
+
+    Some((last_trailing_mark(problem), last_trailing_mark(context)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_and_context_marks, find_marks, problem_and_context_marks, Mark, MarkMatch};
+    use crate::LocationPattern;
+
+    #[test]
+    fn find_marks_trailing() {
+        let error_string = "missing field `a` at line 2 column 12 at line 2 column 3";
+        let mark_matches = find_marks(error_string, &LocationPattern::default());
+
+        assert_eq!(
+            vec![
+                (
+                    Mark::LineColumn {
+                        line: 2,
+                        column: 12
+                    },
+                    17..37,
+                    true
+                ),
+                (Mark::LineColumn { line: 2, column: 3 }, 37..56, true),
+            ],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| (mark_match.mark, mark_match.range, mark_match.is_trailing))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn find_marks_not_trailing_before_comma() {
+        let error_string = "did not find expected key at line 4 column 3, \
+            while parsing a block mapping at position 9";
+        let mark_matches = find_marks(error_string, &LocationPattern::default());
+
+        assert_eq!(
+            vec![
+                (Mark::LineColumn { line: 4, column: 3 }, false),
+                (Mark::Position(9), true),
+            ],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| (mark_match.mark, mark_match.is_trailing))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn error_and_context_marks_column_before_comma() {
+        let error_string = "invalid value at line 4 column 10, expected a string";
+
+        assert_eq!(
+            (
+                Some(Mark::LineColumn {
+                    line: 4,
+                    column: 10
+                }),
+                Vec::new()
+            ),
+            error_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn error_and_context_marks_before_comma_and_trailing() {
+        let error_string =
+            "invalid value at line 4 column 10, while loading a mapping at line 2 column 1";
+
+        assert_eq!(
+            (
+                Some(Mark::LineColumn {
+                    line: 4,
+                    column: 10
+                }),
+                vec![Mark::LineColumn { line: 2, column: 1 }]
+            ),
+            error_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn find_marks_at_start() {
+        let mark_matches = find_marks("at line 2 column 3", &LocationPattern::default());
+
+        assert_eq!(
+            vec![(Mark::LineColumn { line: 2, column: 3 }, 0..18, true)],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| (mark_match.mark, mark_match.range, mark_match.is_trailing))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn find_marks_saturates_overflowing_numbers() {
+        let mark_matches = find_marks(
+            "invalid value at line 999999999999999999999 column 1",
+            &LocationPattern::default(),
+        );
+
+        assert_eq!(
+            vec![Mark::LineColumn {
+                line: usize::MAX,
+                column: 1
+            }],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| mark_match.mark)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn find_marks_skips_missing_numbers() {
+        let mark_matches = find_marks(
+            "invalid value at line  column  at position ",
+            &LocationPattern::default(),
+        );
+
+        assert_eq!(Vec::<MarkMatch>::new(), mark_matches);
+    }
+
+    #[test]
+    fn find_marks_skips_backtick_quoted_text() {
+        let error_string =
+            "unknown field `look at line 1 column 2 at line 3 column 4`, expected `name` at line 2 column 1";
+        let mark_matches = find_marks(error_string, &LocationPattern::default());
+
+        assert_eq!(
+            vec![MarkMatch {
+                mark: Mark::LineColumn { line: 2, column: 1 },
+                range: 75..94,
+                is_trailing: true,
+            }],
+            mark_matches
+        );
+    }
+
+    #[test]
+    fn find_marks_column_without_line() {
+        let error_string = "invalid value at column 7";
+        let mark_matches = find_marks(error_string, &LocationPattern::default());
+
+        assert_eq!(
+            vec![MarkMatch {
+                mark: Mark::LineColumn { line: 1, column: 7 },
+                range: 13..25,
+                is_trailing: true,
+            }],
+            mark_matches
+        );
+    }
+
+    #[test]
+    fn find_marks_column_without_line_ignored_without_delimiter() {
+        let error_string = "invalid column 7 at line 2 column 3";
+        let mark_matches = find_marks(error_string, &LocationPattern::with_delimiter(""));
+
+        assert_eq!(
+            vec![Mark::LineColumn { line: 2, column: 3 }],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| mark_match.mark)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn find_marks_translated() {
+        let location_pattern = LocationPattern {
+            at_line: String::from(" en la línea "),
+            column: String::from(" columna "),
+            at_position: String::from(" en la posición "),
+        };
+        let mark_matches = find_marks(
+            "campo desconocido en la línea 3 columna 5 en la posición 7",
+            &location_pattern,
+        );
+
+        assert_eq!(
+            vec![Mark::LineColumn { line: 3, column: 5 }, Mark::Position(7)],
+            mark_matches
+                .into_iter()
+                .map(|mark_match| mark_match.mark)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn error_and_context_marks_innermost_to_outermost() {
+        let error_string =
+            "missing field `a` at line 6 column 5 at line 4 column 3 at line 2 column 1";
+
+        assert_eq!(
+            (
+                Some(Mark::LineColumn { line: 6, column: 5 }),
+                vec![
+                    Mark::LineColumn { line: 4, column: 3 },
+                    Mark::LineColumn { line: 2, column: 1 },
+                ]
+            ),
+            error_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn error_and_context_marks_repeated_mark_kept_once() {
+        let error_string = "unknown variant `~`, expected `a` \
+            at line 2 column 11 at line 2 column 11 at line 2 column 3";
+
+        assert_eq!(
+            (
+                Some(Mark::LineColumn {
+                    line: 2,
+                    column: 11
+                }),
+                vec![Mark::LineColumn { line: 2, column: 3 }]
+            ),
+            error_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn problem_and_context_marks_while_parsing() {
+        let error_string = "did not find expected ',' or ']' at line 2 column 6, \
+            while parsing a flow sequence at line 1 column 6";
+
+        assert_eq!(
+            Some((
+                Some(Mark::LineColumn { line: 2, column: 6 }),
+                Some(Mark::LineColumn { line: 1, column: 6 }),
+            )),
+            problem_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn problem_and_context_marks_without_context_mark() {
+        let error_string =
+            "did not find expected key at line 4 column 2, while parsing a block mapping";
+
+        assert_eq!(
+            Some((Some(Mark::LineColumn { line: 4, column: 2 }), None)),
+            problem_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn problem_and_context_marks_none_without_while() {
+        let error_string = "missing field `a` at line 2 column 12 at line 2 column 3";
+
+        assert_eq!(
+            None,
+            problem_and_context_marks(error_string, &LocationPattern::default())
+        );
+    }
+}
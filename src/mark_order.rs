@@ -0,0 +1,84 @@
+use crate::mark::Mark;
+
+/// Which of the trailing marks in the error string is the error, and which are
+/// the context.
+///
+/// For example, in the following, `ErrorThenContext` treats line 3 as the
+/// error and line 2 as the context, and `ContextThenError` treats line 2 as
+/// the error and line 3 as the context:
+///
+/// ```text
+/// invalid value at line 3 column 3 at line 2 column 1
+/// ```
+///
+/// With every order, libyaml errors with a `"while parsing ..."` or `"while
+/// scanning ..."` context are split into the problem mark and the context
+/// mark, and those are ordered. For other errors, the location that
+/// `serde_yaml` reports is used instead of the marks in the error string when
+/// it is known.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarkOrder {
+    /// The first mark is the error, and each following mark is a context, from
+    /// the innermost to the outermost.
+    ErrorThenContext,
+    /// The last mark is the error, and each preceding mark is a context, from
+    /// the innermost to the outermost.
+    ContextThenError,
+    /// The order that `serde_yaml` renders the marks in, which is the same as
+    /// [`MarkOrder::ErrorThenContext`].
+    #[default]
+    Auto,
+}
+
+impl MarkOrder {
+    /// Returns the error and context marks, given the marks in the order that
+    /// [`MarkOrder::ErrorThenContext`] reads them.
+    pub(crate) fn order(
+        self,
+        error_mark: Option<Mark>,
+        context_marks: Vec<Mark>,
+    ) -> (Option<Mark>, Vec<Mark>) {
+        match self {
+            MarkOrder::ErrorThenContext | MarkOrder::Auto => (error_mark, context_marks),
+            MarkOrder::ContextThenError => {
+                let mut marks = error_mark.into_iter().chain(context_marks).rev();
+                let error_mark = marks.next();
+                (error_mark, marks.collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MarkOrder;
+    use crate::mark::Mark;
+
+    const LINE_1: Mark = Mark::LineColumn { line: 1, column: 1 };
+    const LINE_2: Mark = Mark::LineColumn { line: 2, column: 1 };
+    const LINE_3: Mark = Mark::LineColumn { line: 3, column: 1 };
+
+    #[test]
+    fn error_then_context_keeps_order() {
+        assert_eq!(
+            (Some(LINE_3), vec![LINE_2, LINE_1]),
+            MarkOrder::ErrorThenContext.order(Some(LINE_3), vec![LINE_2, LINE_1])
+        );
+    }
+
+    #[test]
+    fn context_then_error_reverses_order() {
+        assert_eq!(
+            (Some(LINE_1), vec![LINE_2, LINE_3]),
+            MarkOrder::ContextThenError.order(Some(LINE_3), vec![LINE_2, LINE_1])
+        );
+    }
+
+    #[test]
+    fn context_then_error_none_when_no_marks() {
+        assert_eq!(
+            (None, Vec::new()),
+            MarkOrder::ContextThenError.order(None, Vec::new())
+        );
+    }
+}
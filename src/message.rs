@@ -0,0 +1,805 @@
+//! Functions to extract information from a `serde_yaml` error message.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{mark, LocationPattern};
+
+/// Returns the error string with the marks removed.
+///
+/// Each mark is removed along with the text before its numbers, as long as the
+/// mark is at the end of the string, followed by another mark, or followed by
+/// a `,`. Other occurrences of `" at "` are kept, as they may be part of a
+/// value.
+///
+/// ```text
+/// unknown variant `~`, expected `a` at line 2 column 11 at line 2 column 3
+/// did not find expected key at line 4 column 3, while parsing a block mapping at line 2 column 1
+/// ```
+///
+/// becomes:
+///
+/// ```text
+/// unknown variant `~`, expected `a`
+/// did not find expected key, while parsing a block mapping
+/// ```
+///
+/// The error string is borrowed if it has no marks to remove.
+pub(crate) fn without_marks<'s>(
+    error_string: &'s str,
+    location_pattern: &LocationPattern,
+) -> Cow<'s, str> {
+    let mark_matches = mark::find_marks(error_string, location_pattern)
+        .into_iter()
+        .filter(|mark_match| mark_match.is_location(error_string))
+        .collect::<Vec<_>>();
+    if mark_matches.is_empty() {
+        return Cow::Borrowed(error_string.trim_end());
+    }
+
+    let mut error_message = String::with_capacity(error_string.len());
+    let mut copied_until = 0;
+    mark_matches.into_iter().for_each(|mark_match| {
+        error_message.push_str(&error_string[copied_until..mark_match.range.start]);
+        copied_until = mark_match.range.end;
+    });
+    error_message.push_str(&error_string[copied_until..]);
+    error_message.truncate(error_message.trim_end().len());
+
+    Cow::Owned(error_message)
+}
+
+/// Returns the error string with the marks removed, reusing its allocation if
+/// it has no marks to remove.
+///
+/// See [`without_marks`].
+pub(crate) fn into_without_marks(
+    error_string: String,
+    location_pattern: &LocationPattern,
+) -> String {
+    let error_message_len = match without_marks(&error_string, location_pattern) {
+        Cow::Borrowed(error_message) => Ok(error_message.len()),
+        Cow::Owned(error_message) => Err(error_message),
+    };
+
+    match error_message_len {
+        Ok(error_message_len) => {
+            let mut error_message = error_string;
+            error_message.truncate(error_message_len);
+            error_message
+        }
+        Err(error_message) => error_message,
+    }
+}
+
+/// Returns the backtick quoted identifiers after `", expected "`.
+///
+/// Handles the following forms:
+///
+/// ```text
+/// unknown variant `~`, expected `One`
+/// unknown variant `~`, expected `One` or `Two`
+/// unknown variant `~`, expected one of `a`, `b`, `c`
+/// ```
+///
+/// The `", expected "` and separators are only matched outside backticks, so
+/// identifiers that contain them are not split.
+pub(crate) fn expected(error_message: &str) -> Vec<String> {
+    let Some(expected_index) = find_unquoted(error_message, ", expected ") else {
+        return Vec::new();
+    };
+    let expected = &error_message[expected_index + ", expected ".len()..];
+    let mut rest = expected.strip_prefix("one of ").unwrap_or(expected);
+
+    let mut identifiers = Vec::new();
+    while let Some(quoted) = rest.strip_prefix('`') {
+        let Some((identifier, after)) = quoted.split_once('`') else {
+            break;
+        };
+        identifiers.push(identifier.to_string());
+
+        match after
+            .strip_prefix(", ")
+            .or_else(|| after.strip_prefix(" or "))
+        {
+            Some(after_separator) => rest = after_separator,
+            None => break,
+        }
+    }
+
+    identifiers
+}
+
+/// Returns the closest of the [`expected`] identifiers to the unknown field or
+/// variant, e.g. `name` for:
+///
+/// ```text
+/// unknown field `nam`, expected `name` or `value`
+/// ```
+///
+/// Returns `None` if no identifier is within an edit distance of a third of
+/// the unknown identifier's length, or 1 for short identifiers.
+pub(crate) fn suggestion(error_message: &str) -> Option<String> {
+    let message = without_path(error_message);
+    let unknown = message
+        .strip_prefix("unknown field `")
+        .or_else(|| message.strip_prefix("unknown variant `"))?;
+    let (unknown, _) = unknown.split_once('`')?;
+    let max_distance = (unknown.chars().count() / 3).max(1);
+
+    expected(message)
+        .into_iter()
+        .map(|identifier| (edit_distance(unknown, &identifier), identifier))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, identifier)| identifier)
+}
+
+/// Returns the Levenshtein distance between `a` and `b`, counted in `char`s.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+    let mut current_row = vec![0; b.len() + 1];
+
+    a.chars().enumerate().for_each(|(a_index, a_char)| {
+        current_row[0] = a_index + 1;
+        b.iter().enumerate().for_each(|(b_index, b_char)| {
+            let substitution_cost = usize::from(a_char != *b_char);
+            current_row[b_index + 1] = (previous_row[b_index] + substitution_cost)
+                .min(previous_row[b_index + 1] + 1)
+                .min(current_row[b_index] + 1);
+        });
+        core::mem::swap(&mut previous_row, &mut current_row);
+    });
+
+    previous_row[b.len()]
+}
+
+/// Returns the leading path to the erroneous value and the rest of the message.
+///
+/// For example, `"outer.inner: unknown variant ..."` returns
+/// `(Some("outer.inner"), "unknown variant ...")`.
+///
+/// The prefix before the first `": "` is only treated as a path if it is made
+/// of the characters that `serde_yaml` renders paths with, so that messages
+/// such as `"invalid type: ..."` are not split. See [`is_path`].
+pub(crate) fn path_and_rest(error_message: &str) -> (Option<&str>, &str) {
+    match error_message.split_once(": ") {
+        Some((path, rest)) if is_path(path) => (Some(path), rest),
+        _ => (None, error_message),
+    }
+}
+
+/// Returns whether `text` looks like a `serde_yaml` path, e.g. `"outer.inner"`,
+/// `"items[2].name"`, `".[0]"` or `"outer.?"`.
+///
+/// Keys may only contain alphanumeric characters, `_` and `-`, and indices
+/// must be digits within `[]`.
+fn is_path(text: &str) -> bool {
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            let Some((index, after_index)) = rest[1..].split_once(']') else {
+                return false;
+            };
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return false;
+            }
+            rest = after_index;
+        } else if c.is_alphanumeric() || matches!(c, '.' | '?' | '_' | '-') {
+            rest = &rest[c.len_utf8()..];
+        } else {
+            return false;
+        }
+    }
+
+    !text.is_empty()
+}
+
+/// Returns the leading path to the erroneous value, if any.
+pub(crate) fn path(error_message: &str) -> Option<&str> {
+    path_and_rest(error_message).0
+}
+
+/// Returns the last key of the path, if the path ends in a mapping key.
+///
+/// For example, `"outer.inner"` returns `Some("inner")`, and `"items[2]"`
+/// returns `None`.
+pub(crate) fn path_last_key(path: &str) -> Option<&str> {
+    let last_segment = path.rsplit('.').next()?;
+    (!last_segment.is_empty() && !last_segment.ends_with(']')).then_some(last_segment)
+}
+
+/// Returns the error message without the leading path to the erroneous value.
+pub(crate) fn without_path(error_message: &str) -> &str {
+    path_and_rest(error_message).1
+}
+
+/// Returns the found and expected types of an `invalid type` error.
+///
+/// For example, the following returns `(r#"string "abc""#, "u32")`:
+///
+/// ```text
+/// invalid type: string "abc", expected u32
+/// ```
+pub(crate) fn invalid_type(error_message: &str) -> Option<(&str, &str)> {
+    let found_and_expected = without_path(error_message).strip_prefix("invalid type: ")?;
+    let expected_index = find_unquoted(found_and_expected, ", expected ")?;
+    let found = &found_and_expected[..expected_index];
+    let expected = &found_and_expected[expected_index + ", expected ".len()..];
+
+    Some((found, expected))
+}
+
+/// Returns the field name of a missing field error, e.g. `field_2` for:
+///
+/// ```text
+/// outer: missing field `field_2`
+/// ```
+pub(crate) fn missing_field(error_message: &str) -> Option<&str> {
+    without_path(error_message)
+        .strip_prefix("missing field `")?
+        .split_once('`')
+        .map(|(field, _)| field)
+}
+
+/// Returns the key of a duplicate key error.
+///
+/// Handles the following forms:
+///
+/// ```text
+/// duplicate field `a`
+/// duplicate entry with key "a"
+/// ```
+pub(crate) fn duplicate_key(error_message: &str) -> Option<&str> {
+    let message = without_path(error_message);
+    if let Some(field) = message.strip_prefix("duplicate field `") {
+        field.split_once('`').map(|(key, _)| key)
+    } else {
+        let key = message.strip_prefix("duplicate entry with key \"")?;
+        key.strip_suffix('"')
+    }
+}
+
+/// Returns the unescaped content of the first double quoted value in the
+/// message, e.g. `2023-01-01` for:
+///
+/// ```text
+/// invalid type: string "2023-01-01", expected i64
+/// ```
+///
+/// Values are quoted with Rust `Debug` escapes, so `\"`, `\\`, `\n`, `\r`,
+/// `\t`, `\0`, `\'`, and `\u{..}` are unescaped.
+pub(crate) fn quoted_value(error_message: &str) -> Option<String> {
+    let message = without_path(error_message);
+    let quoted_onwards = &message[message.find('"')? + 1..];
+    let mut value = String::new();
+    let mut chars = quoted_onwards.chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                '0' => value.push('\0'),
+                'u' => {
+                    let code_point = chars
+                        .by_ref()
+                        .skip_while(|c| *c == '{')
+                        .take_while(|c| *c != '}')
+                        .collect::<String>();
+                    let c = u32::from_str_radix(&code_point, 16)
+                        .ok()
+                        .and_then(char::from_u32)?;
+                    value.push(c);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+/// Returns whether the error is from exceeding `serde_yaml`'s recursion limit.
+///
+/// ```text
+/// recursion limit exceeded
+/// ```
+pub(crate) fn is_recursion_limit(error_message: &str) -> bool {
+    without_path(error_message).starts_with("recursion limit exceeded")
+}
+
+/// Returns whether the error is from an alias to an anchor that was not
+/// defined.
+///
+/// ```text
+/// unknown anchor
+/// ```
+pub(crate) fn is_unknown_anchor(error_message: &str) -> bool {
+    without_path(error_message).starts_with("unknown anchor")
+}
+
+/// Returns whether the error is from a tab character used for indentation.
+///
+/// ```text
+/// found a tab character that violates indentation
+/// ```
+pub(crate) fn is_tab_indentation(error_message: &str) -> bool {
+    without_path(error_message).starts_with("found a tab character")
+}
+
+/// Returns whether the error is from libyaml not finding a token that it
+/// expected, e.g.:
+///
+/// ```text
+/// did not find expected ',' or ']'
+/// ```
+pub(crate) fn is_did_not_find_expected(error_message: &str) -> bool {
+    without_path(error_message).starts_with("did not find expected ")
+}
+
+/// Returns whether the error is from the input ending before the YAML is
+/// complete.
+///
+/// ```text
+/// found unexpected end of stream
+/// ```
+///
+/// Quoted values in the message, such as `string "unexpected end of file"`,
+/// are not matched.
+pub(crate) fn is_unexpected_end(error_message: &str) -> bool {
+    find_unquoted(without_path(error_message), "unexpected end of ").is_some()
+}
+
+/// Returns `text` with ANSI escape sequences removed, e.g. the color codes in
+/// `"\x1b[31minvalid value\x1b[0m"`.
+///
+/// Control sequences (`ESC [ ... final byte`) are removed whole. Any other
+/// escape removes the `ESC` and the character after it.
+pub(crate) fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+
+        if chars.next() == Some('[') {
+            // Parameter and intermediate bytes are in `0x20..=0x3F`, and the final
+            // byte is in `0x40..=0x7E`.
+            chars.by_ref().find(|c| ('\x40'..='\x7e').contains(c));
+        }
+    }
+
+    stripped
+}
+
+/// Returns `error_message` truncated to `max_len` characters, with the last
+/// character replaced by `…` if it is truncated.
+///
+/// A `max_len` of `0` returns an empty string, as there is no room for the
+/// `…`.
+pub(crate) fn truncate(mut error_message: String, max_len: usize) -> String {
+    if max_len == 0 {
+        error_message.clear();
+    } else if let Some((truncate_index, _)) = error_message.char_indices().nth(max_len) {
+        let ellipsis_index = error_message[..truncate_index]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index);
+        error_message.truncate(ellipsis_index);
+        error_message.push('…');
+    }
+
+    error_message
+}
+
+/// Returns the byte index of the first `pattern` in `text` that is not within
+/// double quotes or backticks.
+///
+/// Within double quotes, `\` escapes the next character.
+pub(crate) fn find_unquoted(text: &str, pattern: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut char_indices = text.char_indices();
+    while let Some((index, c)) = char_indices.next() {
+        match (quote, c) {
+            (None, _) if text[index..].starts_with(pattern) => return Some(index),
+            (None, '"' | '`') => quote = Some(c),
+            (Some('"'), '\\') => {
+                char_indices.next();
+            }
+            (Some(quote_char), c) if c == quote_char => quote = None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{
+        duplicate_key, edit_distance, expected, find_unquoted, into_without_marks, invalid_type,
+        is_unexpected_end, missing_field, path, path_last_key, quoted_value, strip_ansi,
+        suggestion, truncate, without_marks, without_path,
+    };
+    use crate::LocationPattern;
+
+    #[test]
+    fn expected_two_items() {
+        assert_eq!(
+            vec!["One".to_string(), "Two".to_string()],
+            expected("outer.inner: unknown variant `~`, expected `One` or `Two`")
+        );
+    }
+
+    #[test]
+    fn expected_one_of_comma_separated_items() {
+        assert_eq!(
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            expected("unknown field `d`, expected one of `a`, `b`, `c`")
+        );
+    }
+
+    #[test]
+    fn expected_single_item() {
+        assert_eq!(
+            vec!["One".to_string()],
+            expected("unknown variant `~`, expected `One`")
+        );
+    }
+
+    #[test]
+    fn expected_item_containing_comma() {
+        assert_eq!(
+            vec!["a, b".to_string(), "c or d".to_string()],
+            expected("unknown variant `x, expected y`, expected `a, b` or `c or d`")
+        );
+    }
+
+    #[test]
+    fn expected_empty_when_not_identifiers() {
+        assert_eq!(
+            Vec::<String>::new(),
+            expected("invalid type: string \"abc\", expected u32")
+        );
+    }
+
+    #[test]
+    fn expected_empty_when_absent() {
+        assert_eq!(
+            Vec::<String>::new(),
+            expected("outer: missing field `field_2`")
+        );
+    }
+
+    #[test]
+    fn without_path_strips_path() {
+        assert_eq!(
+            "unknown variant `~`, expected `One` or `Two`",
+            without_path("outer.inner: unknown variant `~`, expected `One` or `Two`")
+        );
+    }
+
+    #[test]
+    fn without_path_keeps_message_without_path() {
+        assert_eq!(
+            "invalid type: string \"abc\", expected u32",
+            without_path("invalid type: string \"abc\", expected u32")
+        );
+    }
+
+    #[test]
+    fn path_dotted() {
+        assert_eq!(
+            Some("outer.inner"),
+            path("outer.inner: unknown variant `~`, expected `One` or `Two`")
+        );
+    }
+
+    #[test]
+    fn path_bracketed() {
+        assert_eq!(
+            Some("items[2].name"),
+            path("items[2].name: invalid type: integer `1`, expected a string")
+        );
+    }
+
+    #[test]
+    fn path_none_when_message_contains_colon() {
+        assert_eq!(None, path("invalid type: string \"a: b\", expected u32"));
+    }
+
+    #[test]
+    fn path_root_sequence_and_unknown() {
+        assert_eq!(Some(".[0]"), path(".[0]: invalid type: unit value"));
+        assert_eq!(Some("outer.?"), path("outer.?: invalid type: unit value"));
+        assert_eq!(Some("kebab-key"), path("kebab-key: missing field `a`"));
+    }
+
+    #[test]
+    fn path_none_when_prefix_has_non_path_characters() {
+        assert_eq!(None, path("https://example.com: not found"));
+        assert_eq!(None, path("`error`: not found"));
+        assert_eq!(None, path("items[a]: invalid value"));
+        assert_eq!(None, path("items[]: invalid value"));
+        assert_eq!(None, path("items[2: invalid value"));
+    }
+
+    #[test]
+    fn path_none_when_absent() {
+        assert_eq!(None, path("missing field `field_2`"));
+    }
+
+    #[test]
+    fn path_last_key_dotted() {
+        assert_eq!(Some("name"), path_last_key("items[2].name"));
+        assert_eq!(Some("outer"), path_last_key("outer"));
+    }
+
+    #[test]
+    fn path_last_key_none_for_index() {
+        assert_eq!(None, path_last_key("outer.items[2]"));
+    }
+
+    #[test]
+    fn without_marks_removes_trailing_marks() {
+        assert_eq!(
+            "unknown variant `~`, expected `a`",
+            without_marks(
+                "unknown variant `~`, expected `a` at line 2 column 11 at line 2 column 3",
+                &LocationPattern::default()
+            )
+        );
+    }
+
+    #[test]
+    fn without_marks_removes_marks_before_comma() {
+        assert_eq!(
+            "did not find expected key, while parsing a block mapping",
+            without_marks(
+                "did not find expected key at line 4 column 3, \
+                while parsing a block mapping at line 2 column 1",
+                &LocationPattern::default()
+            )
+        );
+    }
+
+    #[test]
+    fn without_marks_keeps_delimiter_in_value() {
+        assert_eq!(
+            "invalid type: string \"meet at noon\", expected u32",
+            without_marks(
+                "invalid type: string \"meet at noon\", expected u32 at line 1 column 8",
+                &LocationPattern::default()
+            )
+        );
+    }
+
+    #[test]
+    fn without_marks_translated() {
+        let location_pattern = LocationPattern {
+            at_line: String::from(" en la línea "),
+            column: String::from(" columna "),
+            at_position: String::from(" en la posición "),
+        };
+
+        assert_eq!(
+            "campo desconocido `c` at line 1",
+            without_marks(
+                "campo desconocido `c` at line 1 en la línea 2 columna 3 en la línea 1 columna 1",
+                &location_pattern
+            )
+        );
+    }
+
+    #[test]
+    fn into_without_marks_reuses_allocation_without_marks() {
+        let error_string = String::from("invalid value  ");
+        let error_string_ptr = error_string.as_ptr();
+        let error_message = into_without_marks(error_string, &LocationPattern::default());
+
+        assert_eq!("invalid value", error_message);
+        assert_eq!(error_string_ptr, error_message.as_ptr());
+    }
+
+    #[test]
+    fn into_without_marks_removes_marks() {
+        assert_eq!(
+            "invalid value",
+            into_without_marks(
+                String::from("invalid value at line 3 column 3"),
+                &LocationPattern::default()
+            )
+        );
+    }
+
+    #[test]
+    fn without_marks_borrows_without_marks() {
+        assert!(matches!(
+            without_marks("invalid value", &LocationPattern::default()),
+            Cow::Borrowed("invalid value")
+        ));
+    }
+
+    #[test]
+    fn without_marks_trims_trailing_whitespace() {
+        assert_eq!(
+            "invalid value",
+            without_marks("invalid value  ", &LocationPattern::default())
+        );
+    }
+
+    #[test]
+    fn invalid_type_string() {
+        assert_eq!(
+            Some(("string \"a, expected b\"", "u32")),
+            invalid_type("value: invalid type: string \"a, expected b\", expected u32")
+        );
+    }
+
+    #[test]
+    fn invalid_type_string_with_escaped_quote() {
+        assert_eq!(
+            Some(("string \"a\\\", expected \"", "u32")),
+            invalid_type("invalid type: string \"a\\\", expected \", expected u32")
+        );
+    }
+
+    #[test]
+    fn invalid_type_sequence() {
+        assert_eq!(
+            Some(("sequence", "a string")),
+            invalid_type("invalid type: sequence, expected a string")
+        );
+    }
+
+    #[test]
+    fn invalid_type_none_for_other_errors() {
+        assert_eq!(None, invalid_type("missing field `field_2`"));
+    }
+
+    #[test]
+    fn quoted_value_plain() {
+        assert_eq!(
+            Some("2023-01-01".to_string()),
+            quoted_value("date: invalid type: string \"2023-01-01\", expected i64")
+        );
+    }
+
+    #[test]
+    fn quoted_value_with_escapes() {
+        assert_eq!(
+            Some("say \"hi\"\n\\ é".to_string()),
+            quoted_value(r#"invalid type: string "say \"hi\"\n\\ \u{e9}", expected u32"#)
+        );
+    }
+
+    #[test]
+    fn quoted_value_none_without_quotes() {
+        assert_eq!(
+            None,
+            quoted_value("invalid type: sequence, expected a string")
+        );
+    }
+
+    #[test]
+    fn duplicate_key_field() {
+        assert_eq!(Some("a"), duplicate_key("outer: duplicate field `a`"));
+    }
+
+    #[test]
+    fn duplicate_key_entry() {
+        assert_eq!(
+            Some("a b"),
+            duplicate_key("duplicate entry with key \"a b\"")
+        );
+    }
+
+    #[test]
+    fn duplicate_key_none_for_other_errors() {
+        assert_eq!(None, duplicate_key("missing field `a`"));
+    }
+
+    #[test]
+    fn suggestion_closest_expected() {
+        assert_eq!(
+            Some(String::from("name")),
+            suggestion("unknown field `nam`, expected `value` or `name`")
+        );
+    }
+
+    #[test]
+    fn suggestion_for_variant_with_path() {
+        assert_eq!(
+            Some(String::from("Second")),
+            suggestion("outer: unknown variant `Secnod`, expected one of `First`, `Second`")
+        );
+    }
+
+    #[test]
+    fn suggestion_none_when_not_close() {
+        assert_eq!(
+            None,
+            suggestion("unknown field `colour`, expected `name` or `value`")
+        );
+    }
+
+    #[test]
+    fn suggestion_none_when_not_unknown() {
+        assert_eq!(None, suggestion("missing field `name`"));
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(0, edit_distance("name", "name"));
+        assert_eq!(1, edit_distance("nam", "name"));
+        assert_eq!(2, edit_distance("nmae", "name"));
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+        assert_eq!(4, edit_distance("", "name"));
+    }
+
+    #[test]
+    fn strip_ansi_removes_color_codes() {
+        assert_eq!(
+            "invalid value at line 1 column 2",
+            strip_ansi("\x1b[1;31minvalid value\x1b[0m at line \x1b[33m1\x1b[0m column 2")
+        );
+    }
+
+    #[test]
+    fn truncate_on_char_boundary() {
+        assert_eq!("café…", truncate(String::from("café au lait"), 5));
+        assert_eq!("caf…", truncate(String::from("café au lait"), 4));
+        assert_eq!("café", truncate(String::from("café"), 4));
+    }
+
+    #[test]
+    fn truncate_to_zero_is_empty() {
+        assert_eq!("", truncate(String::from("café"), 0));
+        assert_eq!("", truncate(String::new(), 0));
+        assert_eq!("…", truncate(String::from("café"), 1));
+    }
+
+    #[test]
+    fn strip_ansi_keeps_text_without_escapes() {
+        assert_eq!("unknown field `a`", strip_ansi("unknown field `a`"));
+    }
+
+    #[test]
+    fn missing_field_with_path() {
+        assert_eq!(
+            Some("field_2"),
+            missing_field("outer: missing field `field_2`")
+        );
+    }
+
+    #[test]
+    fn missing_field_none_for_other_errors() {
+        assert_eq!(None, missing_field("unknown field `field_2`"));
+    }
+
+    #[test]
+    fn is_unexpected_end_ignores_quoted_values() {
+        assert!(is_unexpected_end("found unexpected end of stream"));
+        assert!(!is_unexpected_end(
+            "value: invalid value: string \"unexpected end of file\", expected u32"
+        ));
+    }
+
+    #[test]
+    fn find_unquoted_skips_backticks() {
+        assert_eq!(
+            Some(22),
+            find_unquoted("unknown field `a at b` at line 1 column 1", " at ")
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
+use crate::ErrorAndContext;
+
+/// Several [`ErrorAndContext`]s for the same source, rendered as one
+/// diagnostic.
+///
+/// This is useful when a loader tries multiple schemas, so that the error from
+/// each attempt is shown at once. Created with [`ErrorAndContext::merge`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiError {
+    /// The errors, in the order they were merged.
+    errors: Vec<ErrorAndContext>,
+}
+
+impl MultiError {
+    /// Returns this `MultiError` with `error` added.
+    pub fn merge(mut self, error: ErrorAndContext) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// Returns the errors, in the order they were merged.
+    pub fn errors(&self) -> &[ErrorAndContext] {
+        &self.errors
+    }
+}
+
+impl From<Vec<ErrorAndContext>> for MultiError {
+    fn from(errors: Vec<ErrorAndContext>) -> Self {
+        Self { errors }
+    }
+}
+
+impl fmt::Display for MultiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.errors
+            .iter()
+            .enumerate()
+            .try_for_each(|(index, error)| match index {
+                0 => write!(f, "{error}"),
+                _ => write!(f, "\n{error}"),
+            })
+    }
+}
+
+impl std::error::Error for MultiError {}
+
+impl Diagnostic for MultiError {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.errors.iter().find_map(|error| error.source_code())
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let mut labels = self
+            .errors
+            .iter()
+            .flat_map(ErrorAndContext::labels)
+            .peekable();
+
+        labels
+            .peek()
+            .is_some()
+            .then(|| Box::new(labels) as Box<dyn Iterator<Item = LabeledSpan>>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
+    use serde::de::Error as _;
+
+    use crate::ErrorAndContext;
+
+    #[test]
+    fn merge_renders_both_labels() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+        let error_first = ErrorAndContext::new(
+            file_contents,
+            &serde_yaml::Error::custom("not a list at line 2 column 1"),
+        );
+        let error_second = ErrorAndContext::new(
+            file_contents,
+            &serde_yaml::Error::custom("not a string at line 3 column 3"),
+        );
+
+        let multi_error = error_first.merge(error_second);
+        assert_eq!(2, multi_error.labels().unwrap().count());
+
+        let mut rendered = String::new();
+        let report = miette::Report::new(multi_error).with_source_code(file_contents.to_string());
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            r#"
+  × not a list
+  │ not a string
+   ╭─[2:1]
+ 1 │ ---
+ 2 │ outer:
+   · ┬
+   · ╰── not a list
+ 3 │   field_1: 123
+   ·   ┬
+   ·   ╰── not a string
+   ╰────
+"#,
+            rendered
+        );
+    }
+
+    #[test]
+    fn labels_none_without_spans() {
+        let error = serde_yaml::Error::custom("invalid value");
+        let multi_error = ErrorAndContext::new("", &error).merge(ErrorAndContext::new("", &error));
+
+        assert!(multi_error.labels().is_none());
+    }
+}
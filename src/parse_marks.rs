@@ -0,0 +1,87 @@
+use alloc::string::String;
+
+use crate::{line_index::LineIndex, mark, message, LocationPattern};
+
+/// Returns the error byte offset, the error message, and the context byte
+/// offset parsed from an error string.
+///
+/// Use this for error strings from other sources that use the same
+/// `" at line L column C"` and `" at position N"` locations as `serde_yaml`.
+/// The first trailing location is the error, and the next is the context.
+///
+/// This finds the locations the same way as [`ErrorAndContext::new`], but is
+/// not the same result. The libyaml `", while parsing"` ordering, the
+/// [`ErrorAndContextBuilder`] options, and the adjustments for specific
+/// errors, such as duplicate keys, block scalars, tabs, and unknown anchors,
+/// are not applied.
+///
+/// This does not use `serde_yaml` or `std`, and only allocates the message
+/// and line index, so it is available without the default `std` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::parse_marks;
+///
+/// let file_contents = "---\nouter:\n  field_1: 123\n";
+/// let (error_span, error_message, context_span) = parse_marks(
+///     "invalid value at line 3 column 3 at line 2 column 1",
+///     file_contents,
+/// );
+///
+/// assert_eq!(Some(13), error_span);
+/// assert_eq!("invalid value", error_message);
+/// assert_eq!(Some(4), context_span);
+/// ```
+///
+/// [`ErrorAndContext::new`]: crate::ErrorAndContext::new
+/// [`ErrorAndContextBuilder`]: crate::ErrorAndContextBuilder
+pub fn parse_marks(
+    error_string: &str,
+    file_contents: &str,
+) -> (Option<usize>, String, Option<usize>) {
+    let location_pattern = LocationPattern::default();
+    let line_index = LineIndex::new(file_contents);
+    let (error_mark, context_marks) =
+        mark::error_and_context_marks(error_string, &location_pattern);
+
+    (
+        error_mark.map(|mark| mark.offset(&line_index, None)),
+        message::without_marks(error_string, &location_pattern).into_owned(),
+        context_marks
+            .first()
+            .map(|mark| mark.offset(&line_index, None)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceOffset;
+
+    use super::parse_marks;
+
+    #[test]
+    fn parse_marks_error_and_context() {
+        let file_contents = "---\nouter:\n  field_1: 123\n";
+
+        assert_eq!(
+            (
+                Some(SourceOffset::from_location(file_contents, 3, 3).offset()),
+                "outer: invalid value".to_string(),
+                Some(SourceOffset::from_location(file_contents, 2, 1).offset()),
+            ),
+            parse_marks(
+                "outer: invalid value at line 3 column 3 at line 2 column 1",
+                file_contents
+            )
+        );
+    }
+
+    #[test]
+    fn parse_marks_without_marks() {
+        assert_eq!(
+            (None, "invalid value".to_string(), None),
+            parse_marks("invalid value", "value: 1\n")
+        );
+    }
+}
@@ -0,0 +1,80 @@
+/// Segment of the path to the erroneous value, e.g. `items[2].name` has the
+/// segments `Key("items")`, `Index(2)`, `Key("name")`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Key within a mapping.
+    Key(String),
+    /// Index within a sequence.
+    Index(usize),
+}
+
+impl PathSegment {
+    /// Returns the segments of a `serde_yaml` path, e.g. `"a.b[3].c"`.
+    ///
+    /// Keys are separated by `.`, and sequence indices are within `[]`.
+    /// Brackets that do not hold an index are kept as part of the key.
+    pub(crate) fn from_path(path: &str) -> Vec<Self> {
+        path.split('.')
+            .flat_map(|part| {
+                let key_len = part.find('[').unwrap_or(part.len());
+                let (key, indices) = part.split_at(key_len);
+                let indices = indices
+                    .strip_suffix(']')
+                    .and_then(|indices| indices.strip_prefix('['))
+                    .map(|indices| {
+                        indices
+                            .split("][")
+                            .map(str::parse::<usize>)
+                            .collect::<Result<Vec<_>, _>>()
+                    });
+
+                match indices {
+                    Some(Ok(indices)) => (!key.is_empty())
+                        .then(|| PathSegment::Key(key.to_string()))
+                        .into_iter()
+                        .chain(indices.into_iter().map(PathSegment::Index))
+                        .collect::<Vec<_>>(),
+                    Some(Err(_)) | None => vec![PathSegment::Key(part.to_string())],
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathSegment;
+
+    #[test]
+    fn from_path_keys_and_indices() {
+        assert_eq!(
+            vec![
+                PathSegment::Key("a".to_string()),
+                PathSegment::Key("b".to_string()),
+                PathSegment::Index(3),
+                PathSegment::Key("c".to_string()),
+            ],
+            PathSegment::from_path("a.b[3].c")
+        );
+    }
+
+    #[test]
+    fn from_path_nested_indices() {
+        assert_eq!(
+            vec![
+                PathSegment::Index(0),
+                PathSegment::Index(1),
+                PathSegment::Key("name".to_string()),
+            ],
+            PathSegment::from_path("[0][1].name")
+        );
+    }
+
+    #[test]
+    fn from_path_non_index_brackets_kept_in_key() {
+        assert_eq!(
+            vec![PathSegment::Key("a[b]".to_string())],
+            PathSegment::from_path("a[b]")
+        );
+    }
+}
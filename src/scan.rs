@@ -0,0 +1,577 @@
+//! Functions to find YAML constructs within the file contents.
+//!
+//! These are best-effort line based scans, and only understand block
+//! mappings.
+
+use std::ops::Range;
+
+use crate::{line_index, token};
+
+/// A mapping key found within the file contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct KeyOccurrence {
+    /// Byte offset of the key, including its quotes if quoted.
+    pub(crate) offset: usize,
+    /// Number of characters before the key on its line, including any `"- "`
+    /// sequence indicators.
+    pub(crate) indentation: usize,
+}
+
+/// Returns each block mapping key in `file_contents` that is equal to `key`,
+/// in order of appearance.
+///
+/// Plain, single quoted and double quoted keys are matched, without
+/// processing escapes.
+pub(crate) fn key_occurrences(file_contents: &str, key: &str) -> Vec<KeyOccurrence> {
+    lines(file_contents)
+        .filter_map(|(line_start, line)| {
+            let (indentation, content) = key_position(line);
+            let after_key =
+                [("", ""), ("\"", "\""), ("'", "'")]
+                    .into_iter()
+                    .find_map(|(open, close)| {
+                        content
+                            .strip_prefix(open)?
+                            .strip_prefix(key)?
+                            .strip_prefix(close)
+                    })?;
+            let after_colon = after_key.trim_start_matches(' ').strip_prefix(':')?;
+            let is_key = after_colon.is_empty() || after_colon.starts_with([' ', '\t']);
+
+            is_key.then(|| KeyOccurrence {
+                offset: line_start + (line.len() - content.len()),
+                indentation,
+            })
+        })
+        .collect()
+}
+
+/// Returns the first duplicate of a key, and the earlier occurrence of the key
+/// within the same block mapping.
+///
+/// If `mapping_offset` is given, only keys at or after that offset are
+/// considered, as `serde_yaml` reports the start of the mapping.
+pub(crate) fn duplicate_key(
+    file_contents: &str,
+    key: &str,
+    mapping_offset: Option<usize>,
+) -> Option<(KeyOccurrence, KeyOccurrence)> {
+    let mapping_offset = mapping_offset.unwrap_or(0);
+    let key_occurrences = key_occurrences(file_contents, key)
+        .into_iter()
+        .filter(|key_occurrence| key_occurrence.offset >= mapping_offset)
+        .collect::<Vec<_>>();
+    let is_same_mapping = |first: &KeyOccurrence, duplicate: &KeyOccurrence| {
+        first.indentation == duplicate.indentation
+            && lines(&file_contents[first.offset..duplicate.offset])
+                .skip(1)
+                .all(|(_, line)| {
+                    let content = line.trim_start_matches(' ');
+                    content.is_empty()
+                        || content.starts_with('#')
+                        || line.len() - content.len() >= first.indentation
+                })
+    };
+
+    key_occurrences.iter().find_map(|duplicate| {
+        key_occurrences
+            .iter()
+            .take_while(|first| first.offset < duplicate.offset)
+            .find(|first| is_same_mapping(first, duplicate))
+            .map(|first| (*duplicate, *first))
+    })
+}
+
+/// Returns the block mapping `key` whose value contains `value_offset`.
+///
+/// This is the closest `key` at or before `value_offset`, as long as each line
+/// from the key up to `value_offset` is indented more than the key, so that the
+/// value is nested within it.
+pub(crate) fn key_for_value(
+    file_contents: &str,
+    key: &str,
+    value_offset: usize,
+) -> Option<KeyOccurrence> {
+    let value_offset = value_offset.min(file_contents.len());
+    key_occurrences(file_contents, key)
+        .into_iter()
+        .rev()
+        .find(|key_occurrence| key_occurrence.offset <= value_offset)
+        .filter(|key_occurrence| {
+            file_contents
+                .get(key_occurrence.offset..value_offset)
+                .is_some_and(|key_to_value| {
+                    lines(key_to_value).skip(1).all(|(_, line)| {
+                        let content = line.trim_start_matches(' ');
+                        content.is_empty()
+                            || content.starts_with('#')
+                            || line.len() - content.len() > key_occurrence.indentation
+                    })
+                })
+        })
+}
+
+/// Returns the block mapping key at the end of `path`, where each key is in
+/// the block mapping that is the value of the key before it.
+///
+/// The first key is in the top level mapping of the first document.
+pub(crate) fn key_path(file_contents: &str, path: &[&str]) -> Option<KeyOccurrence> {
+    let (first_key, nested_keys) = path.split_first()?;
+    let document_start = document_start(file_contents, 0);
+    let document_line = line_index::line_range(file_contents, document_start);
+    let (indentation, _) = key_position(&file_contents[document_line]);
+    let first = key_occurrences(file_contents, first_key)
+        .into_iter()
+        .find(|key_occurrence| key_occurrence.indentation == indentation)?;
+
+    nested_keys
+        .iter()
+        .zip(path.iter())
+        .try_fold(first, |parent, (key, parent_key)| {
+            // The nested mapping begins on a following line, and its keys are indented
+            // the same as its first key.
+            let mapping_offset = value_offset(file_contents, parent.offset)?;
+            let mapping_line = line_index::line_range(file_contents, mapping_offset);
+            if mapping_line.contains(&parent.offset) {
+                return None;
+            }
+            let (indentation, _) = key_position(&file_contents[mapping_line]);
+
+            key_occurrences(file_contents, key)
+                .into_iter()
+                .find(|key_occurrence| {
+                    key_occurrence.offset > parent.offset
+                        && key_occurrence.indentation == indentation
+                        && key_for_value(file_contents, parent_key, key_occurrence.offset)
+                            == Some(parent)
+                })
+        })
+}
+
+/// Returns the byte offset of the value of the block mapping key at
+/// `key_offset`.
+///
+/// This is the first content after the `:`, which may be on a following line
+/// for nested mappings and sequences.
+pub(crate) fn value_offset(file_contents: &str, key_offset: usize) -> Option<usize> {
+    let (key_line_start, key_line) = lines(file_contents)
+        .take_while(|(line_start, _)| *line_start <= key_offset)
+        .last()?;
+    let after_key = &key_line[key_offset - key_line_start..];
+    let key_len = token::token_len(after_key, 0);
+    let colon_index = key_len + after_key[key_len..].find(':')?;
+    let value = after_key[colon_index + 1..].trim_start_matches([' ', '\t']);
+    if !(value.is_empty() || value.starts_with('#')) {
+        return Some(key_line_start + key_line.len() - value.len());
+    }
+
+    lines(file_contents)
+        .skip_while(|(line_start, _)| *line_start <= key_offset)
+        .find_map(|(line_start, line)| {
+            let content = line.trim_start_matches(' ');
+            (!(content.trim().is_empty() || content.starts_with('#')))
+                .then(|| line_start + (line.len() - content.len()))
+        })
+}
+
+/// Returns the byte range of each document in a multi-document YAML stream.
+///
+/// Each document after the first begins at its `---` marker. A `---` marker
+/// that is only preceded by blank lines, comments, and directives begins the
+/// first document.
+pub(crate) fn document_ranges(file_contents: &str) -> Vec<Range<usize>> {
+    let mut document_starts = vec![0];
+    let mut has_content = false;
+    lines(file_contents).for_each(|(line_start, line)| {
+        let is_marker = line
+            .strip_prefix("---")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+        if is_marker {
+            if has_content {
+                document_starts.push(line_start);
+            }
+            has_content = true;
+        } else {
+            let content = line.trim_start();
+            if !(content.is_empty() || content.starts_with('#') || content.starts_with('%')) {
+                has_content = true;
+            }
+        }
+    });
+
+    document_starts
+        .iter()
+        .zip(document_starts.iter().skip(1).chain([&file_contents.len()]))
+        .map(|(start, end)| *start..*end)
+        .collect()
+}
+
+/// Returns the byte offset of where the document containing `offset` begins.
+///
+/// This is the `---` marker or the first content of the document, skipping
+/// blank lines, comments, and directives.
+pub(crate) fn document_start(file_contents: &str, offset: usize) -> usize {
+    let document_range = document_ranges(file_contents)
+        .into_iter()
+        .rev()
+        .find(|document_range| document_range.start <= offset)
+        .unwrap_or(0..file_contents.len());
+
+    lines(&file_contents[document_range.clone()])
+        .find_map(|(line_start, line)| {
+            let content = line.trim_start();
+            let is_content =
+                !(content.is_empty() || content.starts_with('#') || content.starts_with('%'));
+            is_content.then(|| document_range.start + line_start + (line.len() - content.len()))
+        })
+        .unwrap_or(document_range.start)
+}
+
+/// Returns the byte offset of the content of the block scalar whose `|` or `>`
+/// indicator is at `offset`.
+///
+/// The indentation of the first non-blank line of the body is skipped. Tabs
+/// after the indentation are part of the content.
+pub(crate) fn block_scalar_body(file_contents: &str, offset: usize) -> Option<usize> {
+    let header = file_contents
+        .get(offset..)?
+        .strip_prefix(['|', '>'])?
+        .trim_start_matches(|c: char| c == '+' || c == '-' || c.is_ascii_digit());
+    let mut header_lines = line_index::lines(header);
+    let header_rest = header_lines.next().unwrap_or_default().1.trim();
+    if !(header_rest.is_empty() || header_rest.starts_with('#')) {
+        return None;
+    }
+
+    let body_start = file_contents.len() - header.len() + header_lines.next()?.0;
+    lines(&file_contents[body_start..])
+        .find_map(|(line_start, line)| {
+            let content = line.trim_start_matches(' ');
+            (!content.trim().is_empty()).then(|| line_start + (line.len() - content.len()))
+        })
+        .map(|content_offset| body_start + content_offset)
+}
+
+/// Returns the length of the block mapping whose first key is at `offset`.
+///
+/// The block continues while each line is indented at least as much as the
+/// first key, and ends after the last non-blank, non-comment line. `None` is
+/// returned when `offset` is not the first key on its line.
+pub(crate) fn block_mapping_len(file_contents: &str, offset: usize) -> Option<usize> {
+    let mut lines =
+        lines(file_contents).skip_while(|(line_start, line)| *line_start + line.len() < offset);
+    let (first_line_start, first_line) = lines.next()?;
+    let (indentation, content) = key_position(first_line);
+    if first_line_start + (first_line.len() - content.len()) != offset {
+        return None;
+    }
+
+    let block_end = lines
+        .take_while(|(_, line)| {
+            let content = line.trim_start_matches(' ');
+            let is_document_marker = line.starts_with("---") || line.starts_with("...");
+            content.is_empty()
+                || content.starts_with('#')
+                || (!is_document_marker && line.len() - content.len() >= indentation)
+        })
+        .filter(|(_, line)| {
+            let content = line.trim();
+            !(content.is_empty() || content.starts_with('#'))
+        })
+        .last()
+        .unwrap_or((first_line_start, first_line));
+    let (line_start, line) = block_end;
+
+    Some(line_start + line.trim_end().len() - offset)
+}
+
+/// Returns the byte offset of the first tab in the indentation of the line
+/// containing `offset`.
+pub(crate) fn indentation_tab(file_contents: &str, offset: usize) -> Option<usize> {
+    let line_range = line_index::line_range(file_contents, offset);
+    let line = &file_contents[line_range.clone()];
+    let indentation = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+
+    indentation
+        .find('\t')
+        .map(|tab_index| line_range.start + tab_index)
+}
+
+/// Returns the name of the `*alias` that begins at `offset`.
+pub(crate) fn alias_name(file_contents: &str, offset: usize) -> Option<&str> {
+    let name_onwards = file_contents.get(offset..)?.strip_prefix('*')?;
+    let name = &name_onwards[..name_onwards
+        .find(is_anchor_name_end)
+        .unwrap_or(name_onwards.len())];
+
+    (!name.is_empty()).then_some(name)
+}
+
+/// Returns the byte offset of each `&name` anchor in `file_contents`, in order
+/// of appearance.
+pub(crate) fn anchor_offsets(file_contents: &str, name: &str) -> Vec<usize> {
+    let anchor = format!("&{name}");
+    file_contents
+        .match_indices(&anchor)
+        .map(|(offset, _)| offset)
+        .filter(|offset| {
+            let is_start = file_contents[..*offset]
+                .chars()
+                .next_back()
+                .is_none_or(is_anchor_name_end);
+            let is_end = file_contents[offset + anchor.len()..]
+                .chars()
+                .next()
+                .is_none_or(is_anchor_name_end);
+            is_start && is_end
+        })
+        .collect()
+}
+
+/// Returns whether `c` ends an anchor or alias name.
+fn is_anchor_name_end(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}')
+}
+
+/// Returns the byte offset and text of each line, without line breaks.
+///
+/// A leading byte order mark is not part of the first line.
+fn lines(file_contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    let contents = file_contents
+        .strip_prefix('\u{feff}')
+        .unwrap_or(file_contents);
+    let bom_len = file_contents.len() - contents.len();
+    line_index::lines(contents).map(move |(line_start, line)| (bom_len + line_start, line))
+}
+
+/// Returns the number of characters before a mapping key on the line, and the
+/// text from where the key would begin.
+///
+/// Leading spaces and `"- "` sequence indicators are skipped.
+fn key_position(line: &str) -> (usize, &str) {
+    let mut content = line.trim_start_matches(' ');
+    while let Some(item) = content.strip_prefix("- ") {
+        content = item.trim_start_matches(' ');
+    }
+
+    (line[..line.len() - content.len()].chars().count(), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        alias_name, anchor_offsets, block_mapping_len, block_scalar_body, document_ranges,
+        document_start, duplicate_key, indentation_tab, key_for_value, key_occurrences, key_path,
+        value_offset, KeyOccurrence,
+    };
+
+    #[test]
+    fn key_occurrences_plain_and_quoted() {
+        let file_contents = "a: 1\nouter:\n  \"a\": 2\n  b: a\n- 'a' : 3\nab: 4\n";
+
+        assert_eq!(
+            vec![
+                KeyOccurrence {
+                    offset: 0,
+                    indentation: 0
+                },
+                KeyOccurrence {
+                    offset: 14,
+                    indentation: 2
+                },
+                KeyOccurrence {
+                    offset: 30,
+                    indentation: 2
+                },
+            ],
+            key_occurrences(file_contents, "a")
+        );
+    }
+
+    #[test]
+    fn duplicate_key_in_same_mapping() {
+        let file_contents = "outer:\n  a: 1\nother:\n  a: 2\n  b: 3\n  a: 4\n";
+
+        assert_eq!(
+            Some((
+                KeyOccurrence {
+                    offset: 37,
+                    indentation: 2
+                },
+                KeyOccurrence {
+                    offset: 23,
+                    indentation: 2
+                },
+            )),
+            duplicate_key(file_contents, "a", None)
+        );
+    }
+
+    #[test]
+    fn duplicate_key_after_mapping_offset() {
+        let file_contents = "a: 1\na: 2\nouter:\n  a: 3\n  a: 4\n";
+
+        assert_eq!(
+            Some((
+                KeyOccurrence {
+                    offset: 26,
+                    indentation: 2
+                },
+                KeyOccurrence {
+                    offset: 19,
+                    indentation: 2
+                },
+            )),
+            duplicate_key(file_contents, "a", Some(19))
+        );
+    }
+
+    #[test]
+    fn duplicate_key_none_when_not_repeated() {
+        let file_contents = "outer:\n  a: 1\nother:\n  a: 2\n";
+
+        assert_eq!(None, duplicate_key(file_contents, "a", None));
+    }
+
+    #[test]
+    fn key_for_value_on_same_line() {
+        let file_contents = "field: 1\nouter:\n  field: \"x\"\n";
+
+        assert_eq!(
+            Some(KeyOccurrence {
+                offset: 18,
+                indentation: 2
+            }),
+            key_for_value(file_contents, "field", 25)
+        );
+    }
+
+    #[test]
+    fn key_for_value_on_following_line() {
+        let file_contents = "outer:\n  field:\n    - 1\n";
+
+        assert_eq!(
+            Some(KeyOccurrence {
+                offset: 9,
+                indentation: 2
+            }),
+            key_for_value(file_contents, "field", 22)
+        );
+    }
+
+    #[test]
+    fn key_for_value_none_when_value_not_nested() {
+        let file_contents = "field: 1\nother: 2\n";
+
+        assert_eq!(None, key_for_value(file_contents, "field", 16));
+    }
+
+    #[test]
+    fn key_path_nested_within_parent_value() {
+        let file_contents = "a:\n  b: 1\nouter:\n  a: 2\n  b:\n    c: 3\n";
+
+        assert_eq!(
+            Some(KeyOccurrence {
+                offset: 26,
+                indentation: 2
+            }),
+            key_path(file_contents, &["outer", "b"])
+        );
+        assert_eq!(
+            Some(KeyOccurrence {
+                offset: 5,
+                indentation: 2
+            }),
+            key_path(file_contents, &["a", "b"])
+        );
+        assert_eq!(None, key_path(file_contents, &["outer", "c"]));
+        assert_eq!(None, key_path(file_contents, &[]));
+    }
+
+    #[test]
+    fn value_offset_on_same_or_following_line() {
+        let file_contents = "a: 1 # one\nb: # comment\n\n  - 2\n\"c:d\": 3\n";
+
+        assert_eq!(Some(3), value_offset(file_contents, 0));
+        assert_eq!(Some(27), value_offset(file_contents, 11));
+        assert_eq!(Some(38), value_offset(file_contents, 31));
+        assert_eq!(None, value_offset("a:\n", 0));
+    }
+
+    #[test]
+    fn alias_name_until_whitespace_or_flow_indicator() {
+        let file_contents = "a: *x\nb: [*yz, 1]\n";
+
+        assert_eq!(Some("x"), alias_name(file_contents, 3));
+        assert_eq!(Some("yz"), alias_name(file_contents, 10));
+        assert_eq!(None, alias_name(file_contents, 0));
+    }
+
+    #[test]
+    fn anchor_offsets_match_whole_name() {
+        let file_contents = "a: &x 1\nb: &xy 2\nc: [&x 3]\nd: a&x\n";
+
+        assert_eq!(vec![3, 21], anchor_offsets(file_contents, "x"));
+    }
+
+    #[test]
+    fn document_ranges_split_at_markers() {
+        let file_contents = "# comment\n---\na: 1\n--- \nb: 2\n---\n";
+
+        assert_eq!(vec![0..19, 19..29, 29..33], document_ranges(file_contents));
+    }
+
+    #[test]
+    fn document_ranges_without_leading_marker() {
+        let file_contents = "a: 1\n---\nb: 2\n";
+
+        assert_eq!(vec![0..5, 5..14], document_ranges(file_contents));
+    }
+
+    #[test]
+    fn document_start_skips_comments_and_directives() {
+        let file_contents = "a: 1\n---\n# comment\n%YAML 1.2\n\n  b: [1,\n";
+
+        assert_eq!(0, document_start(file_contents, 2));
+        assert_eq!(5, document_start(file_contents, file_contents.len()));
+        assert_eq!(23, document_start("# comment\n%YAML 1.2\n\n  b: [1,\n", 33));
+    }
+
+    #[test]
+    fn block_scalar_body_skips_indentation() {
+        let file_contents = "text: |+2 # comment\n\n  \ta\n  b\n";
+
+        assert_eq!(Some(23), block_scalar_body(file_contents, 6));
+        assert_eq!(None, block_scalar_body(file_contents, 0));
+        assert_eq!(None, block_scalar_body("text: | a\n", 6));
+    }
+
+    #[test]
+    fn block_mapping_len_covers_more_indented_lines() {
+        let file_contents = "- name:\n    first: a\n\n    # comment\n    last: b\n  other: 1\n";
+
+        assert_eq!(Some(35), block_mapping_len(file_contents, 12));
+        assert_eq!(Some(8), block_mapping_len("name:\n  first: a\n---\n", 8));
+        assert_eq!(None, block_mapping_len(file_contents, 14));
+    }
+
+    #[test]
+    fn indentation_tab_skips_leading_spaces() {
+        let file_contents = "a:\n  b: 1\n  \tc: 2\n";
+
+        assert_eq!(Some(12), indentation_tab(file_contents, 10));
+        assert_eq!(Some(12), indentation_tab(file_contents, 14));
+        assert_eq!(None, indentation_tab("a:\n  b:\tc\n", 5));
+    }
+
+    #[test]
+    fn key_occurrences_after_byte_order_mark() {
+        assert_eq!(
+            vec![KeyOccurrence {
+                offset: 3,
+                indentation: 0
+            }],
+            key_occurrences("\u{feff}a: 1\n", "a")
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{line_index, token};
+
+/// How the length of the [`error_span`] is inferred.
+///
+/// [`error_span`]: crate::ErrorAndContext::error_span
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SpanLen {
+    /// Underline the character at the span.
+    #[default]
+    Char,
+    /// Underline the YAML token at the span, e.g. the whole scalar or key.
+    ///
+    /// When a mapping was found where another type was expected, the whole
+    /// block mapping is underlined. When a field is missing from a flow
+    /// mapping, the whole `{ ... }` is underlined.
+    Token,
+    /// Underline from the span to the end of its line.
+    Line,
+    /// Underline the number of bytes returned by the function, given the file
+    /// contents and the byte offset of the span.
+    ///
+    /// Two `Custom` strategies are equal if their functions have the same
+    /// address.
+    Custom(fn(&str, usize) -> usize),
+}
+
+impl SpanLen {
+    /// Returns the length in bytes of the span at `offset`.
+    pub(crate) fn span_len(self, file_contents: &str, offset: usize) -> usize {
+        match self {
+            SpanLen::Char => file_contents
+                .get(offset..)
+                .and_then(|onwards| onwards.chars().next())
+                .map_or(0, char::len_utf8),
+            SpanLen::Token => token::token_len(file_contents, offset),
+            SpanLen::Line => line_index::line_range(file_contents, offset)
+                .end
+                .saturating_sub(offset),
+            SpanLen::Custom(span_len_fn) => span_len_fn(file_contents, offset),
+        }
+    }
+}
+
+impl PartialEq for SpanLen {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SpanLen::Char, SpanLen::Char)
+            | (SpanLen::Token, SpanLen::Token)
+            | (SpanLen::Line, SpanLen::Line) => true,
+            (SpanLen::Custom(span_len_fn), SpanLen::Custom(span_len_fn_other)) => {
+                std::ptr::fn_addr_eq(*span_len_fn, *span_len_fn_other)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SpanLen {}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanLen;
+
+    #[test]
+    fn char_covers_one_character() {
+        assert_eq!(1, SpanLen::Char.span_len("value: abc\n", 7));
+        assert_eq!(2, SpanLen::Char.span_len("value: éa\n", 7));
+        assert_eq!(0, SpanLen::Char.span_len("value: abc\n", 11));
+    }
+
+    #[test]
+    fn line_covers_to_end_of_line() {
+        assert_eq!(5, SpanLen::Line.span_len("value: abc d\nnext: 1\n", 7));
+        assert_eq!(3, SpanLen::Line.span_len("value: abc\r\n", 7));
+    }
+
+    #[test]
+    fn custom_calls_function() {
+        assert_eq!(
+            4,
+            SpanLen::Custom(|file_contents, offset| file_contents.len() - offset)
+                .span_len("value: abc\n", 7)
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::ops::Range;
+
+use crate::{message, ErrorAndContext, LocationPattern};
+
+/// A deserialization error that can be located in the source it was
+/// deserialized from.
+///
+/// This is implemented for `serde_yaml::Error`, and `toml::de::Error` when the
+/// `"toml"` feature is enabled. Use [`ErrorAndContext::from_spanned`] to get
+/// the error location and message for either.
+pub trait SpannedError {
+    /// Returns the error message, without its location.
+    fn message(&self) -> String;
+
+    /// Returns the byte range of the error within `file_contents`, if it can
+    /// be located.
+    fn span(&self, file_contents: &str) -> Option<Range<usize>>;
+}
+
+/// The span is found from the `Display` string of the error, as the
+/// `location()` may be incorrect.
+impl SpannedError for serde_yaml::Error {
+    fn message(&self) -> String {
+        message::into_without_marks(self.to_string(), &LocationPattern::default())
+    }
+
+    fn span(&self, file_contents: &str) -> Option<Range<usize>> {
+        ErrorAndContext::new(file_contents, self).error_range()
+    }
+}
+
+#[cfg(feature = "toml")]
+impl SpannedError for toml::de::Error {
+    fn message(&self) -> String {
+        toml::de::Error::message(self).to_string()
+    }
+
+    fn span(&self, _file_contents: &str) -> Option<Range<usize>> {
+        toml::de::Error::span(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::SpannedError;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    struct Config {
+        value: u32,
+    }
+
+    #[test]
+    fn serde_yaml_message_and_span() {
+        let file_contents = "value: abc\n";
+        let error = serde_yaml::from_str::<Config>(file_contents).unwrap_err();
+
+        assert_eq!(
+            (
+                String::from(r#"value: invalid type: string "abc", expected u32"#),
+                Some(7..8)
+            ),
+            (error.message(), error.span(file_contents))
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_message_and_span() {
+        let file_contents = "value = \"abc\"\n";
+        let error = toml::from_str::<Config>(file_contents).unwrap_err();
+
+        assert_eq!(
+            (
+                String::from(r#"invalid type: string "abc", expected u32"#),
+                Some(8..13)
+            ),
+            (
+                SpannedError::message(&error),
+                SpannedError::span(&error, file_contents)
+            )
+        );
+    }
+}
@@ -0,0 +1,201 @@
+/// Returns the length in bytes of the YAML token that begins at `offset`.
+///
+/// Quoted scalars include their surrounding quotes. Plain scalars end at
+/// whitespace, a flow indicator (`,`, `[`, `]`, `{`, `}`), or a `:` that is
+/// followed by whitespace.
+///
+/// Returns `0` if `offset` is out of bounds or at whitespace.
+pub(crate) fn token_len(file_contents: &str, offset: usize) -> usize {
+    let Some(token_onwards) = file_contents.get(offset..) else {
+        return 0;
+    };
+
+    match token_onwards.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            quoted_len(token_onwards, quote).unwrap_or_else(|| plain_len(token_onwards))
+        }
+        Some(indicator @ (',' | '[' | ']' | '{' | '}')) => indicator.len_utf8(),
+        Some(_) => plain_len(token_onwards),
+        None => 0,
+    }
+}
+
+/// Returns the byte offset of the start of the token that `offset` is within.
+///
+/// Scans back over characters until whitespace or a flow indicator (`,`, `[`,
+/// `]`, `{`, `}`). Returns `offset` if it is out of bounds or at whitespace.
+pub(crate) fn token_start(file_contents: &str, offset: usize) -> usize {
+    let is_delimiter = |c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}');
+    let at_token = file_contents
+        .get(offset..)
+        .and_then(|onwards| onwards.chars().next())
+        .is_some_and(|c| !is_delimiter(c));
+    if !at_token {
+        return offset;
+    }
+
+    file_contents[..offset]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| is_delimiter(*c))
+        .map_or(0, |(index, c)| index + c.len_utf8())
+}
+
+/// Returns the length in bytes of the flow mapping or flow sequence that
+/// begins at `offset`, including the closing `}` or `]`.
+///
+/// Nested collections and quoted scalars are skipped. Returns `None` if
+/// `offset` is not at a `{` or `[`, or the collection is not closed.
+pub(crate) fn flow_collection_len(file_contents: &str, offset: usize) -> Option<usize> {
+    let collection_onwards = file_contents.get(offset..)?;
+    if !collection_onwards.starts_with(['{', '[']) {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut index = 0;
+    while let Some(c) = collection_onwards[index..].chars().next() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            quote @ ('"' | '\'') => {
+                index += quoted_len(&collection_onwards[index..], quote)?;
+                continue;
+            }
+            _ => {}
+        }
+        index += c.len_utf8();
+    }
+
+    None
+}
+
+/// Returns the length of the quoted scalar at the start of `token_onwards`,
+/// including both quotes, or `None` if the quote is not terminated.
+fn quoted_len(token_onwards: &str, quote: char) -> Option<usize> {
+    let mut char_indices = token_onwards.char_indices().skip(1).peekable();
+    while let Some((index, c)) = char_indices.next() {
+        match c {
+            // `\"` is an escaped quote in double quoted scalars.
+            '\\' if quote == '"' => {
+                char_indices.next();
+            }
+            // `''` is an escaped quote in single quoted scalars.
+            '\'' if quote == '\'' && matches!(char_indices.peek(), Some((_, '\''))) => {
+                char_indices.next();
+            }
+            c if c == quote => return Some(index + c.len_utf8()),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the length of the plain scalar at the start of `token_onwards`.
+fn plain_len(token_onwards: &str) -> usize {
+    let mut char_indices = token_onwards.char_indices().peekable();
+    while let Some((index, c)) = char_indices.next() {
+        let is_delimiter = match c {
+            ',' | '[' | ']' | '{' | '}' => true,
+            ':' => char_indices
+                .peek()
+                .is_none_or(|(_, next)| next.is_whitespace()),
+            c => c.is_whitespace(),
+        };
+
+        if is_delimiter {
+            return index;
+        }
+    }
+
+    token_onwards.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flow_collection_len, token_len, token_start};
+
+    #[test]
+    fn token_start_within_plain_scalar() {
+        assert_eq!(2, token_start("  field_1: 123", 6));
+    }
+
+    #[test]
+    fn flow_collection_len_to_matching_close() {
+        let file_contents = "a: {b: [1, \"]}\"], c: '{'}, d: 2";
+
+        assert_eq!(Some(22), flow_collection_len(file_contents, 3));
+        assert_eq!(Some(9), flow_collection_len(file_contents, 7));
+        assert_eq!(None, flow_collection_len(file_contents, 0));
+        assert_eq!(None, flow_collection_len("a: {b: 1", 3));
+    }
+
+    #[test]
+    fn token_start_at_start_of_token() {
+        assert_eq!(2, token_start("  field_1: 123", 2));
+    }
+
+    #[test]
+    fn token_start_after_flow_indicator() {
+        assert_eq!(4, token_start("[a, bcd]", 6));
+    }
+
+    #[test]
+    fn token_start_at_start_of_file() {
+        assert_eq!(0, token_start("field_1: 123", 4));
+    }
+
+    #[test]
+    fn token_start_unchanged_at_whitespace_or_out_of_bounds() {
+        assert_eq!(
+            (3, 20),
+            (token_start("abc def", 3), token_start("abc def", 20))
+        );
+    }
+
+    #[test]
+    fn plain_scalar_ends_at_whitespace() {
+        assert_eq!(3, token_len("abc def", 0));
+    }
+
+    #[test]
+    fn plain_key_ends_at_colon() {
+        assert_eq!(7, token_len("field_1: 123", 0));
+    }
+
+    #[test]
+    fn plain_scalar_includes_colon_not_followed_by_whitespace() {
+        assert_eq!(18, token_len("https://github.com", 0));
+    }
+
+    #[test]
+    fn plain_scalar_ends_at_flow_indicator() {
+        assert_eq!(1, token_len("{a: 1, b: 2}", 10));
+    }
+
+    #[test]
+    fn flow_indicator_is_one_byte() {
+        assert_eq!(1, token_len("[1, 2]", 0));
+    }
+
+    #[test]
+    fn single_quoted_scalar_includes_escaped_quotes() {
+        assert_eq!(10, token_len("'it''s ok' # comment", 0));
+    }
+
+    #[test]
+    fn unterminated_quote_falls_back_to_plain_scalar() {
+        assert_eq!(4, token_len("\"abc def", 0));
+    }
+
+    #[test]
+    fn out_of_bounds_offset_is_zero_len() {
+        assert_eq!(0, token_len("abc", 4));
+    }
+}
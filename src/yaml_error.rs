@@ -0,0 +1,156 @@
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
+
+use crate::ErrorAndContext;
+
+/// A `serde_yaml` error with the YAML that failed to deserialize.
+///
+/// This implements [`Diagnostic`], so it can be returned from a loader with
+/// `?` and rendered by miette without keeping the source alongside it.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::YamlError;
+///
+/// fn load(file_contents: String) -> Result<u32, YamlError> {
+///     serde_yaml::from_str::<u32>(&file_contents)
+///         .map_err(|error| YamlError::from_serde(file_contents.clone(), error))
+/// }
+///
+/// let report = miette::Report::new(load(String::from("abc")).unwrap_err());
+/// ```
+#[derive(Debug)]
+pub struct YamlError {
+    /// The YAML that was deserialized.
+    source: String,
+    /// The error location and message.
+    ///
+    /// This is boxed so that `Result<T, YamlError>` stays small.
+    error_and_context: Box<ErrorAndContext>,
+    /// The underlying `serde_yaml` error.
+    error: serde_yaml::Error,
+}
+
+impl YamlError {
+    /// Returns a new `YamlError` for the error from deserializing `source`.
+    pub fn from_serde(source: impl Into<String>, error: serde_yaml::Error) -> Self {
+        let source = source.into();
+        let error_and_context = Box::new(ErrorAndContext::new(&source, &error));
+
+        Self {
+            source,
+            error_and_context,
+            error,
+        }
+    }
+
+    /// Returns the YAML that was deserialized.
+    pub fn source_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the error location and message.
+    pub fn error_and_context(&self) -> &ErrorAndContext {
+        &self.error_and_context
+    }
+
+    /// Returns the underlying `serde_yaml` error.
+    pub fn serde_error(&self) -> &serde_yaml::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error_and_context.fmt(f)
+    }
+}
+
+/// The underlying `serde_yaml` error is not the [`source`], as its message
+/// is the same as this error's with the locations that this error removes.
+/// Use [`YamlError::serde_error`] to access it.
+///
+/// [`source`]: std::error::Error::source
+impl std::error::Error for YamlError {}
+
+/// The [`severity`] is [`Severity::Warning`] for recoverable errors such as
+/// unknown fields, even though deserialization failed.
+///
+/// [`severity`]: ErrorAndContext::severity
+impl Diagnostic for YamlError {
+    fn severity(&self) -> Option<Severity> {
+        Some(self.error_and_context.severity())
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Diagnostic::help(self.error_and_context.as_ref())
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Diagnostic::labels(self.error_and_context.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{GraphicalReportHandler, GraphicalTheme};
+    use serde::{Deserialize, Serialize};
+
+    use super::YamlError;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    struct Config {
+        value: u32,
+    }
+
+    fn load(file_contents: &str) -> Result<Config, YamlError> {
+        let config = serde_yaml::from_str::<Config>(file_contents)
+            .map_err(|error| YamlError::from_serde(file_contents, error))?;
+        Ok(config)
+    }
+
+    #[test]
+    fn propagates_and_renders() {
+        let report = miette::Report::new(load("value: abc\n").unwrap_err());
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+
+        assert_eq!(
+            r#"
+  × value: invalid type: string "abc", expected u32
+   ╭────
+ 1 │ value: abc
+   ·        ┬
+   ·        ╰── value: invalid type: string "abc", expected u32
+   ╰────
+  help: expected u32
+"#,
+            rendered
+        );
+    }
+
+    #[test]
+    fn serde_error_not_in_source_chain() {
+        let yaml_error = load("value: abc\n").unwrap_err();
+
+        assert!(std::error::Error::source(&yaml_error).is_none());
+        assert_eq!(
+            "value: invalid type: string \"abc\", expected u32 at line 1 column 8",
+            yaml_error.serde_error().to_string()
+        );
+    }
+
+    #[test]
+    fn ok_when_valid() {
+        assert_eq!(Config { value: 1 }, load("value: 1\n").unwrap());
+    }
+}
@@ -0,0 +1,95 @@
+use miette::Severity;
+
+use crate::message;
+
+/// Category of a `serde_yaml` error, classified from the error message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum YamlErrorKind {
+    /// A required field is missing, e.g. ``missing field `field_2` ``.
+    MissingField,
+    /// A field is not recognized, e.g. ``unknown field `nam`, expected `name`
+    /// ``.
+    UnknownField,
+    /// An enum variant is not recognized, e.g. ``unknown variant `~`, expected
+    /// `One` or `Two` ``.
+    UnknownVariant,
+    /// The value has the wrong type, e.g. `invalid type: string "abc", expected
+    /// u32`.
+    InvalidType,
+    /// A key is specified more than once, e.g. ``duplicate field `a` ``.
+    DuplicateKey,
+    /// A tab character is used for indentation, e.g. `found a tab character
+    /// that violates indentation`.
+    TabIndentation,
+    /// Any other error, holding the error message.
+    Other(String),
+}
+
+impl YamlErrorKind {
+    /// Returns the kind of error based on the error message.
+    pub(crate) fn from_message(error_message: &str) -> Self {
+        let message = message::without_path(error_message);
+        if message.starts_with("missing field ") {
+            YamlErrorKind::MissingField
+        } else if message.starts_with("unknown field ") {
+            YamlErrorKind::UnknownField
+        } else if message.starts_with("unknown variant ") {
+            YamlErrorKind::UnknownVariant
+        } else if message.starts_with("invalid type: ") {
+            YamlErrorKind::InvalidType
+        } else if message.starts_with("duplicate field ") || message.starts_with("duplicate entry ")
+        {
+            YamlErrorKind::DuplicateKey
+        } else if message::is_tab_indentation(error_message) {
+            YamlErrorKind::TabIndentation
+        } else {
+            YamlErrorKind::Other(error_message.to_string())
+        }
+    }
+
+    /// Returns how severe this kind of error is.
+    ///
+    /// Unknown fields are a [`Severity::Warning`], as they are recoverable:
+    /// removing the field, or allowing unknown fields, lets the document
+    /// deserialize. Other errors are a [`Severity::Error`].
+    ///
+    /// A [`Severity::Warning`] does not mean that deserialization succeeded, as
+    /// `serde_yaml` still failed with the error.
+    pub fn severity(&self) -> Severity {
+        match self {
+            YamlErrorKind::UnknownField => Severity::Warning,
+            YamlErrorKind::MissingField
+            | YamlErrorKind::UnknownVariant
+            | YamlErrorKind::InvalidType
+            | YamlErrorKind::DuplicateKey
+            | YamlErrorKind::TabIndentation
+            | YamlErrorKind::Other(_) => Severity::Error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::Severity;
+
+    use super::YamlErrorKind;
+
+    #[test]
+    fn severity_warning_for_unknown_field() {
+        assert_eq!(Severity::Warning, YamlErrorKind::UnknownField.severity());
+    }
+
+    #[test]
+    fn severity_error_for_other_kinds() {
+        [
+            YamlErrorKind::MissingField,
+            YamlErrorKind::UnknownVariant,
+            YamlErrorKind::InvalidType,
+            YamlErrorKind::DuplicateKey,
+            YamlErrorKind::TabIndentation,
+            YamlErrorKind::Other("did not find expected key".to_string()),
+        ]
+        .iter()
+        .for_each(|kind| assert_eq!(Severity::Error, kind.severity(), "{kind:?}"));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::ErrorAndContext;
+
+/// Adds the error location and message to a `serde_yaml` result.
+///
+/// The [`ErrorAndContext`] is only built when the result is an error.
+///
+/// # Examples
+///
+/// ```rust
+/// use yaml_error_context_hack::YamlResultExt;
+///
+/// let file_contents = "abc";
+/// let error_and_context = serde_yaml::from_str::<u32>(file_contents)
+///     .with_yaml_context(file_contents)
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     "invalid type: string \"abc\", expected u32",
+///     error_and_context.error_message
+/// );
+/// ```
+pub trait YamlResultExt<T> {
+    /// Returns the value, or the [`ErrorAndContext`] for the error from
+    /// deserializing `source`.
+    ///
+    /// The error holds onto `source`, so that it can be rendered without the
+    /// original file contents. See [`ErrorAndContext::new_owned`].
+    ///
+    /// Use [`YamlError`] instead if a small error type is needed.
+    ///
+    /// [`YamlError`]: crate::YamlError
+    #[allow(clippy::result_large_err)]
+    fn with_yaml_context(self, source: &str) -> Result<T, ErrorAndContext>;
+}
+
+impl<T> YamlResultExt<T> for Result<T, serde_yaml::Error> {
+    fn with_yaml_context(self, source: &str) -> Result<T, ErrorAndContext> {
+        self.map_err(|error| ErrorAndContext::new_owned(source, &error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceOffset;
+
+    use super::YamlResultExt;
+
+    #[test]
+    fn ok_unchanged() {
+        let file_contents = "123";
+
+        assert_eq!(
+            123,
+            serde_yaml::from_str::<u32>(file_contents)
+                .with_yaml_context(file_contents)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn err_with_context() {
+        let file_contents = "value: abc\n";
+        let error_and_context =
+            serde_yaml::from_str::<std::collections::BTreeMap<String, u32>>(file_contents)
+                .with_yaml_context(file_contents)
+                .unwrap_err();
+
+        assert_eq!(Some(SourceOffset::from(7)), error_and_context.error_span);
+        assert_eq!(Some(file_contents), error_and_context.source());
+    }
+}